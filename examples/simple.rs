@@ -32,5 +32,6 @@ fn main() {
 
     let mut config = Config::new(Box::new(EmbeddedResourceLoader));
     config.pan = true;
+    config.title = Some("Rounded rectangle".into());
     show(scene, config);
 }
@@ -0,0 +1,92 @@
+use pathfinder_gl::GLFramebuffer;
+use pathfinder_geometry::vector::Vector2F;
+use pathfinder_color::ColorU;
+use winit::window::Theme;
+use crate::{RenderCacheStats, PostProcess};
+
+// everything a rendered frame depends on, as far as the viewer knows
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct CacheKey {
+    pub page: usize,
+    pub scale: i32, // log2 of the scale in steps of 1/1024
+    pub view_center: Vector2F,
+    pub window_size: Vector2F,
+    pub background: ColorU, // from `Interactive::background`
+    pub theme: Option<Theme>,
+    pub post_process: Option<PostProcess>,
+}
+impl CacheKey {
+    pub fn quantize_scale(scale: f32) -> i32 {
+        (scale.log2() * 1024.0).round() as i32
+    }
+}
+
+// rendered frames, least recently used first
+pub struct RenderCache {
+    entries: Vec<(CacheKey, GLFramebuffer)>,
+    budget: usize,
+    hits: u64,
+    misses: u64,
+}
+impl RenderCache {
+    pub fn new(budget: usize) -> RenderCache {
+        RenderCache {
+            entries: vec![],
+            budget,
+            hits: 0,
+            misses: 0,
+        }
+    }
+    pub fn enabled(&self) -> bool {
+        self.budget > 0
+    }
+    pub fn get(&mut self, key: &CacheKey) -> Option<&GLFramebuffer> {
+        match self.entries.iter().position(|(k, _)| k == key) {
+            Some(idx) => {
+                self.hits += 1;
+                let entry = self.entries.remove(idx);
+                self.entries.push(entry);
+                self.entries.last().map(|(_, framebuffer)| framebuffer)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+    // frames that do not fit into the budget at all are dropped
+    pub fn insert(&mut self, key: CacheKey, framebuffer: GLFramebuffer) {
+        let size = frame_bytes(&framebuffer);
+        if size > self.budget {
+            return;
+        }
+        self.entries.retain(|(k, _)| *k != key);
+        while self.bytes() + size > self.budget {
+            self.entries.remove(0);
+        }
+        self.entries.push((key, framebuffer));
+    }
+    pub fn fits(&self, bytes: usize) -> bool {
+        bytes <= self.budget
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+    fn bytes(&self) -> usize {
+        self.entries.iter().map(|(_, framebuffer)| frame_bytes(framebuffer)).sum()
+    }
+    pub fn stats(&self) -> RenderCacheStats {
+        RenderCacheStats {
+            entries: self.entries.len(),
+            bytes: self.bytes(),
+            budget: self.budget,
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+}
+
+fn frame_bytes(framebuffer: &GLFramebuffer) -> usize {
+    let size = framebuffer.texture.size;
+    size.x() as usize * size.y() as usize * 4
+}
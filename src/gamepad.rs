@@ -0,0 +1,66 @@
+use gilrs::{Gilrs, Event, EventType, Button, Axis};
+use pathfinder_geometry::vector::Vector2F;
+use std::time::{Instant, Duration};
+use crate::{Context, Interactive};
+
+// how often connected gamepads are checked for events
+pub const POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+const DEAD_ZONE: f32 = 0.15;
+const PAN_SPEED: f32 = 800.0; // pixels per second at full deflection
+const ZOOM_SPEED: f32 = 2.0; // log2 zoom per second at full deflection
+
+pub struct Gamepads {
+    gilrs: Gilrs,
+    last_poll: Instant,
+}
+impl Gamepads {
+    pub fn new() -> Option<Gamepads> {
+        match Gilrs::new() {
+            Ok(gilrs) => Some(Gamepads { gilrs, last_poll: Instant::now() }),
+            Err(e) => {
+                warn!("gamepads are not available: {}", e);
+                None
+            }
+        }
+    }
+    pub fn any_connected(&self) -> bool {
+        self.gilrs.gamepads().next().is_some()
+    }
+
+    // handle pending events and apply the sticks.
+    // returns true while a stick is deflected, so the caller keeps polling.
+    pub fn poll<T: Interactive + ?Sized>(&mut self, item: &mut T, ctx: &mut Context) -> bool {
+        while let Some(Event { event, .. }) = self.gilrs.next_event() {
+            match event {
+                EventType::ButtonPressed(Button::DPadRight | Button::DPadDown | Button::RightTrigger, _) => ctx.next_page(),
+                EventType::ButtonPressed(Button::DPadLeft | Button::DPadUp | Button::LeftTrigger, _) => ctx.prev_page(),
+                EventType::AxisChanged(..) => {}
+                event => item.gamepad_input(ctx, event),
+            }
+        }
+
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_poll).min(POLL_INTERVAL * 4).as_secs_f32();
+        self.last_poll = now;
+
+        let mut active = false;
+        for (_, gamepad) in self.gilrs.gamepads() {
+            let pan = dead_zone(Vector2F::new(gamepad.value(Axis::LeftStickX), -gamepad.value(Axis::LeftStickY)));
+            let zoom = dead_zone(Vector2F::new(0.0, gamepad.value(Axis::RightStickY))).y();
+            if !pan.is_zero() && ctx.config.pan {
+                ctx.move_by(pan * (PAN_SPEED * dt / ctx.scale));
+                active = true;
+            }
+            if zoom != 0.0 && ctx.config.zoom {
+                ctx.zoom_by(zoom * ZOOM_SPEED * dt);
+                active = true;
+            }
+        }
+        active
+    }
+}
+
+fn dead_zone(v: Vector2F) -> Vector2F {
+    if v.length() < DEAD_ZONE { Vector2F::default() } else { v }
+}
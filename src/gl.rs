@@ -1,7 +1,8 @@
 
 use std::{ffi::CStr, num::NonZeroU32};
 
-use pathfinder_gl::{GLDevice, GLVersion};
+use pathfinder_gl::{GLDevice, GLVersion, GLFramebuffer};
+use pathfinder_gpu::{Device, TextureFormat};
 use pathfinder_renderer::{
     concurrent::{
         rayon::RayonExecutor,
@@ -13,69 +14,175 @@ use pathfinder_renderer::{
         renderer::Renderer
     },
     scene::Scene,
-    options::{BuildOptions}
+    options::{BuildOptions, RenderTransform}
 };
+use pathfinder_color::ColorF;
 use pathfinder_geometry::{
     vector::{Vector2F, Vector2I},
-    rect::RectF
+    rect::{RectF, RectI},
+    transform2d::Transform2F,
 };
 
-use glutin::{context::{ContextApi, Version, PossiblyCurrentContext}, config::{ConfigTemplate, ConfigTemplateBuilder, Api}, prelude::{GlConfig, GlDisplay, NotCurrentGlContextSurfaceAccessor}, display::{GetGlDisplay, Display}, surface::{GlSurface, Surface, WindowSurface}};
+use glutin::{context::{ContextApi, Version, PossiblyCurrentContext}, config::{ConfigTemplate, ConfigTemplateBuilder, Api, ColorBufferType}, prelude::{GlConfig, GlDisplay, NotCurrentGlContextSurfaceAccessor}, display::{GetGlDisplay, Display}, surface::{GlSurface, Surface, WindowSurface, SwapInterval}};
 use winit::{
     event_loop::EventLoop,
     window::{WindowBuilder, Window},
     dpi::{PhysicalSize},
 };
 use gl;
-use crate::Config;
-use crate::util::round_v_to_16;
+use crate::{Config, RawImage, PixelFormat, PostProcess, RenderCacheStats, InitError, TextAaMode};
+use crate::post::PostProcessor;
+use crate::cache::{RenderCache, CacheKey};
+use crate::util::{round_v_to_tile, tile_size, render_view_box, check_resources, prewarm_scene};
 use glutin_winit::{DisplayBuilder, GlWindow as GlutinGlWindow};
 use raw_window_handle::HasRawWindowHandle;
 
+// fields are dropped in order: everything holding GL objects has to come before
+// the context, surface and window at the end
 pub struct GlWindow {
-    gl_context: PossiblyCurrentContext,
-    gl_surface: Surface<WindowSurface>,
     proxy: SceneProxy,
+    thumbnail_proxy: SceneProxy, // builds thumbnails while `proxy` is used for frames
+    thumbnail_build: Option<ThumbnailBuild>,
+    band_proxy: Option<SceneProxy>, // holds the scene of `progressive`, created on first use
+    threads: bool,
     renderer: Renderer<GLDevice>,
     framebuffer_size: Vector2I,
     window_size: Vector2F,
+    texture_target: Option<GLFramebuffer>,
+    progressive: Option<Progressive>,
+    accumulator: Option<GLFramebuffer>,
+    band_target: Option<GLFramebuffer>,
+    region_targets: Vec<GLFramebuffer>,
+    thumbnail_target: Option<GLFramebuffer>,
+    post_processor: Option<PostProcessor>,
+    post_target: Option<GLFramebuffer>,
+    render_level: RendererLevel,
+    exact_size: bool,
+    text_aa: TextAaMode,
+    unmultiply: bool,
+    hold_frame: bool,
+    clear: bool,
+    render_cache: RenderCache,
+    config_info: GlConfigInfo,
+    gl_context: PossiblyCurrentContext,
+    gl_surface: Surface<WindowSurface>,
     window: Window,
 }
+
+// what was actually obtained from the driver
+#[derive(Clone, Debug)]
+pub struct GlConfigInfo {
+    pub api: GLVersion,
+    pub color_bits: Option<(u8, u8, u8)>, // red, green, blue. None for luminance buffers.
+    pub alpha_bits: u8,
+    pub depth_bits: u8,
+    pub stencil_bits: u8,
+    pub samples: u8,
+    pub srgb: bool,
+    pub transparency: Option<bool>,
+    pub hardware_accelerated: bool,
+    pub vendor: String,   // GL_VENDOR
+    pub renderer: String, // GL_RENDERER, usually names the GPU
+}
+
+// height of the bands for progressive rendering, a multiple of the tile size
+const PROGRESSIVE_BAND_HEIGHT: i32 = 256;
+
+// the scene is in `band_proxy`, each band only builds the tiles inside of it
+struct Progressive {
+    next_band: i32,
+}
+
+// a thumbnail given to `start_thumbnail`, waiting for `finish_thumbnail`
+struct ThumbnailBuild {
+    size: Vector2I, // including the supersampling
+    padded_size: Vector2I,
+    supersample: u32,
+}
 impl GlWindow {
-    pub fn new<T>(event_loop: &EventLoop<T>, title: String, window_size: Vector2F, config: &Config) -> Self {
+    pub fn new<T>(event_loop: &EventLoop<T>, title: String, window_size: Vector2F, config: &Config) -> Result<Self, InitError> {
+        check_resources(&*config.resource_loader, config.render_level)?;
+
         let window_builder = WindowBuilder::new()
             .with_title(title)
             .with_decorations(config.borders)
             .with_inner_size(PhysicalSize::new(window_size.x() as f64, window_size.y() as f64))
-            .with_transparent(config.transparent);
+            .with_transparent(config.transparent)
+            .with_maximized(config.start_maximized);
+        let window_builder = match config.min_window_size {
+            Some(size) => window_builder.with_min_inner_size(physical_size(size)),
+            None => window_builder
+        };
+        let window_builder = match config.max_window_size_limit {
+            Some(size) => window_builder.with_max_inner_size(physical_size(size)),
+            None => window_builder
+        };
+
+        #[cfg(any(target_os="linux", target_os="dragonfly", target_os="freebsd", target_os="netbsd", target_os="openbsd"))]
+        let window_builder = match config.app_id {
+            Some(ref app_id) => {
+                // the app id on Wayland, WM_CLASS on X11
+                use winit::platform::{wayland::WindowBuilderExtWayland, x11::WindowBuilderExtX11};
+                let window_builder = WindowBuilderExtWayland::with_name(window_builder, app_id.clone(), app_id.clone());
+                WindowBuilderExtX11::with_name(window_builder, app_id.clone(), app_id.clone())
+            }
+            None => window_builder
+        };
 
         let (glutin_gl_version, renderer_gl_version, api) = match config.render_level {
             RendererLevel::D3D9 => (Version::new(3, 0), GLVersion::GLES3, Api::GLES3),
             RendererLevel::D3D11 => (Version::new(4, 3), GLVersion::GL4, Api::OPENGL),
         };
-        let template_builder = ConfigTemplateBuilder::new().with_alpha_size(8).with_api(api);
+        let (color_bits, alpha_bits) = config.color_depth.bits();
+        // ask for the 8 bit minimum, so displays without deeper configs still match.
+        // `exact_depth` ranks the requested depth first.
+        let template_builder = ConfigTemplateBuilder::new()
+            .with_buffer_type(ColorBufferType::Rgb { r_size: 8, g_size: 8, b_size: 8 })
+            .with_alpha_size(alpha_bits)
+            .with_api(api);
+        // only used for ranking, filtering on it would reject software-only GL stacks
+        let prefer_hardware = config.prefer_hardware;
+        let exact_depth = |config: &glutin::config::Config| {
+            config.color_buffer_type() == Some(ColorBufferType::Rgb { r_size: color_bits, g_size: color_bits, b_size: color_bits })
+                && config.alpha_size() == alpha_bits
+        };
         let display_builder = DisplayBuilder::new().with_window_builder(Some(window_builder));
         let (mut window, gl_config) = display_builder.build(event_loop, template_builder, |configs| {
+            // rank lexicographically so the pick does not depend on the order of `configs`
             configs
-            .reduce(|accum, config| {
-                let transparency_check = config.supports_transparency().unwrap_or(false)
-                    & !accum.supports_transparency().unwrap_or(false);
-
-                if transparency_check || config.num_samples() > accum.num_samples() {
-                    config
-                } else {
-                    accum
-                }
-            })
+            .max_by_key(|config| (
+                exact_depth(config),
+                prefer_hardware && config.hardware_accelerated(),
+                config.supports_transparency().unwrap_or(false),
+                config.num_samples(),
+            ))
             .unwrap()
         }).unwrap();
         let mut window = window.unwrap();
 
         let raw_window_handle = window.raw_window_handle();
 
+        let mut config_info = GlConfigInfo {
+            api: renderer_gl_version,
+            color_bits: match gl_config.color_buffer_type() {
+                Some(ColorBufferType::Rgb { r_size, g_size, b_size }) => Some((r_size, g_size, b_size)),
+                _ => None,
+            },
+            alpha_bits: gl_config.alpha_size(),
+            depth_bits: gl_config.depth_size(),
+            stencil_bits: gl_config.stencil_size(),
+            samples: gl_config.num_samples(),
+            srgb: gl_config.srgb_capable(),
+            transparency: gl_config.supports_transparency(),
+            hardware_accelerated: gl_config.hardware_accelerated(),
+            vendor: String::new(),
+            renderer: String::new(),
+        };
+
         let gl_display = gl_config.display();
         
         let context_attributes = glutin::context::ContextAttributesBuilder::new()
+            .with_debug(config.gl_debug)
             .build(Some(raw_window_handle));
         
         let attrs = window.build_surface_attributes(<_>::default());
@@ -92,20 +199,44 @@ impl GlWindow {
             .unwrap()
         };
 
+        // wait for vsync, so there is at most one frame per refresh
+        let swap_interval = match config.vsync {
+            true => SwapInterval::Wait(NonZeroU32::new(1).unwrap()),
+            false => SwapInterval::DontWait,
+        };
+        if let Err(e) = gl_surface.set_swap_interval(&current_context, swap_interval) {
+            warn!("failed to set swap interval: {}", e);
+        }
+
         gl::load_with(|ptr: &str| gl_display.get_proc_address(unsafe { CStr::from_ptr(ptr.as_ptr().cast()) }));
+
+        if config.gl_debug {
+            install_debug_callback();
+        }
+
+        config_info.vendor = gl_string(gl::VENDOR);
+        config_info.renderer = gl_string(gl::RENDERER);
+        info!("using GL config {:?}", config_info);
+        if config.transparent && config_info.transparency != Some(true) {
+            warn!("transparency was requested, but the GL config does not support it");
+        }
+        if config_info.color_bits != Some((color_bits, color_bits, color_bits)) || config_info.alpha_bits != alpha_bits {
+            warn!("{:?} was requested, but no GL config has that depth", config.color_depth);
+        }
         
         let dpi = window.scale_factor() as f32;
-        let proxy = match config.threads {
+        let new_proxy = || match config.threads {
             true => SceneProxy::new(config.render_level, RayonExecutor),
             false => SceneProxy::new(config.render_level, SequentialExecutor)
         };
+        let (proxy, thumbnail_proxy) = (new_proxy(), new_proxy());
         let framebuffer_size = (window_size * dpi).to_i32();
         // Create a Pathfinder renderer.
         let render_mode = RendererMode { level: config.render_level };
         let render_options = RendererOptions {
             dest:  DestFramebuffer::full_window(framebuffer_size),
-            background_color: Some(config.background),
-            show_debug_ui: false,
+            background_color: if config.clear { Some(config.background) } else { None },
+            show_debug_ui: config.debug_ui,
         };
 
 
@@ -115,24 +246,349 @@ impl GlWindow {
             render_options,
         );
 
-        GlWindow {
+        Ok(GlWindow {
             gl_context: current_context,
             gl_surface,
             proxy,
+            thumbnail_proxy,
+            thumbnail_build: None,
+            band_proxy: None,
+            threads: config.threads,
             renderer,
             framebuffer_size,
             window_size,
             window,
+            texture_target: None,
+            progressive: None,
+            accumulator: None,
+            band_target: None,
+            region_targets: vec![],
+            thumbnail_target: None,
+            post_processor: config.post_process.map(|effect| PostProcessor::new(effect, config.render_level)),
+            post_target: None,
+            render_level: config.render_level,
+            exact_size: config.exact_size,
+            text_aa: config.text_aa,
+            unmultiply: config.unmultiply,
+            hold_frame: config.hold_frame,
+            clear: config.clear,
+            render_cache: RenderCache::new(config.render_cache_budget),
+            config_info,
+        })
+    }
+    pub fn render(&mut self, scene: Scene, options: BuildOptions) {
+        self.draw_frame(scene, options);
+        self.gl_surface.swap_buffers(&self.gl_context).unwrap();
+    }
+    // draw `scene` into the back buffer, through the post process effect if there is one
+    fn draw_frame(&mut self, mut scene: Scene, options: BuildOptions) {
+        scene.set_view_box(self.window_view_box());
+
+        if self.post_processor.is_some() {
+            // render into a texture first and run the effect from there into the window
+            let framebuffer = self.offscreen_target(self.post_target.take(), self.framebuffer_size);
+            let framebuffer = self.render_offscreen(scene, options, framebuffer);
+            if let Some(ref post_processor) = self.post_processor {
+                post_processor.apply(framebuffer.texture.gl_texture, self.framebuffer_size);
+            }
+            self.post_target = Some(framebuffer);
+        } else {
+            self.proxy.replace_scene(scene);
+            self.proxy.build_and_render(&mut self.renderer, options);
         }
     }
-    pub fn render(&mut self, mut scene: Scene, options: BuildOptions) {
-        scene.set_view_box(RectF::new(Vector2F::default(), self.framebuffer_size.to_f32()));
-        self.proxy.replace_scene(scene);
 
-        self.proxy.build_and_render(&mut self.renderer, options);
+    // render each scene into its rectangle of the window (in pixels, from the top left) and present them
+    pub fn render_regions(&mut self, scenes: Vec<(Scene, RectI)>, options: BuildOptions) {
+        let window_height = self.framebuffer_size.y();
+        if let Some(color) = self.renderer.options().background_color {
+            unsafe {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+                gl::ClearColor(color.r(), color.g(), color.b(), color.a());
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+            }
+        }
+        let mut targets = std::mem::take(&mut self.region_targets);
+        let mut used = Vec::with_capacity(scenes.len());
+        for (mut scene, rect) in scenes {
+            if rect.width() <= 0 || rect.height() <= 0 {
+                continue;
+            }
+            let padded_size = round_v_to_tile(rect.size(), tile_size(self.render_level));
+            let framebuffer = self.offscreen_target(targets.pop(), padded_size);
+            scene.set_view_box(render_view_box(rect.size(), padded_size, self.exact_size));
+            let options = BuildOptions {
+                transform: RenderTransform::Transform2D(Transform2F::from_translation(-rect.origin().to_f32())),
+                .. options
+            };
+            let framebuffer = self.render_offscreen(scene, options, framebuffer);
+            unsafe {
+                // GL counts rows from the bottom
+                blit(framebuffer.gl_framebuffer, 0,
+                    RectI::from_points(Vector2I::new(0, padded_size.y() - rect.height()), Vector2I::new(rect.width(), padded_size.y())),
+                    RectI::from_points(Vector2I::new(rect.min_x(), window_height - rect.max_y()), Vector2I::new(rect.max_x(), window_height - rect.min_y()))
+                );
+            }
+            used.push(framebuffer);
+        }
+        self.region_targets = used;
         self.gl_surface.swap_buffers(&self.gl_context).unwrap();
     }
-    
+
+    // present the frame kept for `key`, returns false if there is none
+    pub fn present_cached(&mut self, key: &CacheKey) -> bool {
+        let size = self.framebuffer_size;
+        let framebuffer = match self.render_cache.get(key) {
+            Some(framebuffer) if framebuffer.texture.size == size => framebuffer.gl_framebuffer,
+            _ => return false
+        };
+        unsafe {
+            blit(framebuffer, 0, RectI::new(Vector2I::default(), size), RectI::new(Vector2I::default(), size));
+        }
+        self.gl_surface.swap_buffers(&self.gl_context).unwrap();
+        true
+    }
+
+    // like `render`, and keep the frame for `key`
+    pub fn render_cached(&mut self, mut scene: Scene, options: BuildOptions, key: CacheKey) {
+        let size = self.framebuffer_size;
+        let bytes = size.x() as usize * size.y() as usize * 4;
+        let uncachable = self.post_processor.is_some() || self.renderer.options().show_debug_ui;
+        if uncachable || !self.render_cache.fits(bytes) {
+            return self.render(scene, options);
+        }
+        scene.set_view_box(self.window_view_box());
+        let framebuffer = self.offscreen_target(None, size);
+        let framebuffer = self.render_offscreen(scene, options, framebuffer);
+        unsafe {
+            blit(framebuffer.gl_framebuffer, 0, RectI::new(Vector2I::default(), size), RectI::new(Vector2I::default(), size));
+        }
+        self.render_cache.insert(key, framebuffer);
+        self.gl_surface.swap_buffers(&self.gl_context).unwrap();
+    }
+    pub fn render_cache_enabled(&self) -> bool {
+        self.render_cache.enabled()
+    }
+    pub fn render_cache_stats(&self) -> RenderCacheStats {
+        self.render_cache.stats()
+    }
+    pub fn clear_render_cache(&mut self) {
+        self.render_cache.clear();
+    }
+
+    pub fn set_post_process(&mut self, effect: Option<PostProcess>) {
+        if self.post_processor.as_ref().map(|p| p.effect()) != effect {
+            self.post_processor = effect.map(|effect| PostProcessor::new(effect, self.render_level));
+        }
+        if effect.is_none() {
+            self.post_target = None;
+        }
+    }
+
+    // render into the back buffer and read it back without presenting.
+    // like `render`, it goes through the post process effect, so captures match the window.
+    pub fn render_to_buffer(&mut self, scene: Scene, options: BuildOptions) -> RawImage {
+        self.draw_frame(scene, options);
+        let image = self.read_pixels();
+        self.readback(image)
+    }
+
+    // render `scene`, in pixels, offscreen into an image of `size`.
+    // with `supersample` > 1 it is rendered that many times larger and scaled down.
+    pub fn render_to_image(&mut self, mut scene: Scene, size: Vector2I, supersample: u32) -> RawImage {
+        let supersample = supersample.max(1);
+        let full_size = size * supersample as i32;
+        let padded_size = round_v_to_tile(full_size, tile_size(self.render_level));
+        scene.set_view_box(render_view_box(full_size, padded_size, self.exact_size));
+        let tr = Transform2F::from_scale(supersample as f32);
+        let options = self.text_aa.build_options(RenderTransform::Transform2D(tr));
+        let framebuffer = self.offscreen_target(None, padded_size);
+        let framebuffer = self.render_offscreen(scene, options, framebuffer);
+        let image = read_pixels(framebuffer.gl_framebuffer, padded_size, full_size);
+        self.readback(image.downsample(supersample))
+    }
+
+    // render into a texture owned by the window, for use by other GL code.
+    // the texture stays valid until the next call.
+    pub fn render_to_texture(&mut self, mut scene: Scene, options: BuildOptions, size: Vector2I) -> (gl::types::GLuint, Vector2I) {
+        let padded_size = round_v_to_tile(size, tile_size(self.render_level));
+        let framebuffer = self.offscreen_target(self.texture_target.take(), padded_size);
+
+        scene.set_view_box(render_view_box(size, padded_size, self.exact_size));
+        let framebuffer = self.render_offscreen(scene, options, framebuffer);
+
+        let texture = framebuffer.texture.gl_texture;
+        self.texture_target = Some(framebuffer);
+        (texture, padded_size)
+    }
+
+    fn window_view_box(&self) -> RectF {
+        render_view_box(self.visible_size(), self.framebuffer_size, self.exact_size)
+    }
+
+    // reuse `framebuffer` if it has the right size
+    fn offscreen_target(&self, framebuffer: Option<GLFramebuffer>, size: Vector2I) -> GLFramebuffer {
+        match framebuffer {
+            Some(framebuffer) if framebuffer.texture.size == size => framebuffer,
+            _ => {
+                let device = self.renderer.device();
+                let texture = device.create_texture(TextureFormat::RGBA8, size);
+                device.create_framebuffer(texture)
+            }
+        }
+    }
+
+    fn render_offscreen(&mut self, scene: Scene, options: BuildOptions, framebuffer: GLFramebuffer) -> GLFramebuffer {
+        self.proxy.replace_scene(scene);
+        let proxy = &mut self.proxy;
+        draw_offscreen(&mut self.renderer, framebuffer, self.framebuffer_size, |renderer| proxy.build_and_render(renderer, options))
+    }
+
+    // start rendering `scene` in bands. call `render_next_band` until it returns false.
+    // the scene is sent to the builder once, the bands only change the transform.
+    pub fn render_progressive(&mut self, mut scene: Scene) {
+        let band_size = Vector2I::new(self.framebuffer_size.x(), PROGRESSIVE_BAND_HEIGHT);
+        scene.set_view_box(RectF::new(Vector2F::default(), band_size.to_f32()));
+        let (level, threads) = (self.render_level, self.threads);
+        let proxy = self.band_proxy.get_or_insert_with(|| match threads {
+            true => SceneProxy::new(level, RayonExecutor),
+            false => SceneProxy::new(level, SequentialExecutor)
+        });
+        proxy.replace_scene(scene);
+        self.progressive = Some(Progressive {
+            next_band: 0
+        });
+        self.clear_accumulator();
+    }
+    pub fn progressive_pending(&self) -> bool {
+        self.progressive.is_some()
+    }
+
+    // renders the next band into the accumulator and presents it, with `hold_frame` only the last one.
+    // returns true if there are bands left.
+    pub fn render_next_band(&mut self) -> bool {
+        let size = self.framebuffer_size;
+        let accumulator_size = self.accumulator.as_ref().map(|fb| fb.texture.size);
+        if accumulator_size != Some(size) {
+            // resized in the middle, start over
+            self.clear_accumulator();
+            if let Some(ref mut job) = self.progressive {
+                job.next_band = 0;
+            }
+        }
+        let band = match self.progressive {
+            Some(ref mut job) => {
+                job.next_band += 1;
+                job.next_band - 1
+            }
+            None => return false
+        };
+
+        let band_size = Vector2I::new(size.x(), PROGRESSIVE_BAND_HEIGHT);
+        let top = band * PROGRESSIVE_BAND_HEIGHT;
+        let options = self.text_aa.build_options(RenderTransform::Transform2D(Transform2F::from_translation(Vector2F::new(0.0, -top as f32))));
+        let band_target = self.offscreen_target(self.band_target.take(), band_size);
+        let proxy = self.band_proxy.as_mut().unwrap();
+        let band_target = draw_offscreen(&mut self.renderer, band_target, size, |renderer| proxy.build_and_render(renderer, options));
+
+        let done = top + PROGRESSIVE_BAND_HEIGHT >= size.y();
+        let present = done || !self.hold_frame;
+        let accumulator = self.accumulator.as_ref().unwrap().gl_framebuffer;
+        unsafe {
+            // GL counts rows from the bottom
+            blit(band_target.gl_framebuffer, accumulator,
+                RectI::new(Vector2I::default(), band_size),
+                RectI::new(Vector2I::new(0, size.y() - top - band_size.y()), band_size)
+            );
+        }
+        if present {
+            let accumulator = self.accumulator.as_ref().unwrap();
+            match self.post_processor {
+                Some(ref post_processor) => post_processor.apply(accumulator.texture.gl_texture, size),
+                None => unsafe {
+                    blit(accumulator.gl_framebuffer, 0, RectI::new(Vector2I::default(), size), RectI::new(Vector2I::default(), size));
+                }
+            }
+        }
+        self.band_target = Some(band_target);
+        if present {
+            self.gl_surface.swap_buffers(&self.gl_context).unwrap();
+        }
+
+        if done {
+            self.progressive = None;
+        }
+        !done
+    }
+
+    fn clear_accumulator(&mut self) {
+        let accumulator = self.offscreen_target(self.accumulator.take(), self.framebuffer_size);
+        let color = self.renderer.options().background_color.unwrap_or(ColorF::transparent_black());
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, accumulator.gl_framebuffer);
+            gl::ClearColor(color.r(), color.g(), color.b(), color.a());
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        self.accumulator = Some(accumulator);
+    }
+
+    fn read_pixels(&self) -> RawImage {
+        // the framebuffer is padded to the tile size, only report the visible part
+        if self.config_info.samples > 1 {
+            // reading a multisampled framebuffer directly fails (GLES) or gives the wrong result,
+            // resolve it into a single sampled one first
+            return resolve_multisampled(self.framebuffer_size, self.resolve_format(), |framebuffer| {
+                read_pixels(framebuffer, self.framebuffer_size, self.visible_size())
+            });
+        }
+        read_pixels(0, self.framebuffer_size, self.visible_size())
+    }
+    // the blit only works between matching formats on GLES, so resolve into the window's own
+    fn resolve_format(&self) -> gl::types::GLenum {
+        match self.config_info.color_bits {
+            Some((10, 10, 10)) if self.config_info.alpha_bits == 2 => gl::RGB10_A2,
+            _ => gl::RGBA8,
+        }
+    }
+    fn readback(&self, mut image: RawImage) -> RawImage {
+        if self.unmultiply {
+            image.unmultiply();
+        }
+        image
+    }
+    fn visible_size(&self) -> Vector2I {
+        Vector2I::new(self.window_size.x().ceil() as i32, self.window_size.y().ceil() as i32)
+    }
+
+    // start building the thumbnail of `scene` (in the coordinates of its view box), scaled to fit `size`
+    // at `supersample` times the size, to be box filtered down.
+    // the build runs on the thread pool of the scene proxy, `finish_thumbnail` does the GPU pass.
+    pub fn start_thumbnail(&mut self, mut scene: Scene, size: Vector2I, supersample: u32) {
+        let supersample = supersample.max(1);
+        let size = size * supersample as i32;
+        let view_box = crate::view_box(&scene);
+        let s = size.to_f32() * view_box.size().recip();
+        let tr = Transform2F::from_scale(s.x().min(s.y())) * Transform2F::from_translation(-view_box.origin());
+
+        let padded_size = round_v_to_tile(size, tile_size(self.render_level));
+        scene.set_view_box(render_view_box(size, padded_size, self.exact_size));
+        let options = self.text_aa.build_options(RenderTransform::Transform2D(tr));
+        self.thumbnail_proxy.replace_scene(scene);
+        self.thumbnail_proxy.build(options);
+        self.thumbnail_build = Some(ThumbnailBuild { size, padded_size, supersample });
+    }
+    // render the thumbnail given to `start_thumbnail`, waits for its build if it is not done yet
+    pub fn finish_thumbnail(&mut self) -> Option<RawImage> {
+        let build = self.thumbnail_build.take()?;
+        let framebuffer = self.offscreen_target(self.thumbnail_target.take(), build.padded_size);
+        let proxy = &mut self.thumbnail_proxy;
+        let framebuffer = draw_offscreen(&mut self.renderer, framebuffer, self.framebuffer_size, |renderer| proxy.render(renderer));
+        let image = read_pixels(framebuffer.gl_framebuffer, build.padded_size, build.size);
+        self.thumbnail_target = Some(framebuffer);
+        Some(self.readback(image.downsample(build.supersample)))
+    }
+
     pub fn resize(&mut self, size: Vector2F) {
         if size != self.window_size {
             self.window.set_inner_size(PhysicalSize::new(size.x() as u32, size.y() as u32));
@@ -142,20 +598,54 @@ impl GlWindow {
     }
     // size changed, update GL context
     pub fn resized(&mut self, size: Vector2F) {
-        // pathfinder does not like scene sizes that are now a multiple of the tile size (16).
-        let new_framebuffer_size = round_v_to_16(size.to_i32());
+        // the surface can not have a zero size, keep the old one while minimized
+        if size.x() < 1.0 || size.y() < 1.0 {
+            return;
+        }
+        // also when the user resized the window, `visible_size` depends on it
+        self.window_size = size;
+        // pathfinder does not like scene sizes that are not a multiple of the tile size.
+        let new_framebuffer_size = round_v_to_tile(size.to_i32(), tile_size(self.render_level));
         if new_framebuffer_size != self.framebuffer_size {
             self.framebuffer_size = new_framebuffer_size;
             self.gl_surface.resize(&self.gl_context, NonZeroU32::new(self.framebuffer_size.x() as u32).unwrap(), NonZeroU32::new(self.framebuffer_size.y() as u32).unwrap());
             self.renderer.options_mut().dest = DestFramebuffer::full_window(new_framebuffer_size);
         }
     }
+    pub fn build_options(&self) -> BuildOptions {
+        self.text_aa.build_options(RenderTransform::default())
+    }
+    pub fn config_info(&self) -> &GlConfigInfo {
+        &self.config_info
+    }
+    pub fn set_debug_ui(&mut self, show: bool) {
+        self.renderer.options_mut().show_debug_ui = show;
+    }
+    // render a small scene offscreen, so shaders are compiled and uploaded before the first frame
+    pub fn prewarm(&mut self) {
+        let size = tile_size(self.render_level) * 2;
+        let scene = prewarm_scene(RectF::new(Vector2F::default(), size.to_f32()));
+        let framebuffer = self.offscreen_target(None, size);
+        let options = self.build_options();
+        self.render_offscreen(scene, options, framebuffer);
+    }
+    pub fn set_background(&mut self, color: ColorF) {
+        if self.clear {
+            self.renderer.options_mut().background_color = Some(color);
+        }
+    }
     pub fn scale_factor(&self) -> f32 {
         self.window.scale_factor() as f32
     }
     pub fn request_redraw(&self) {
         self.window.request_redraw();
     }
+    pub fn set_min_size(&self, size: Option<Vector2F>) {
+        self.window.set_min_inner_size(size.map(physical_size));
+    }
+    pub fn set_max_size(&self, size: Option<Vector2F>) {
+        self.window.set_max_inner_size(size.map(physical_size));
+    }
     pub fn framebuffer_size(&self) -> Vector2I {
         self.framebuffer_size
     }
@@ -163,3 +653,119 @@ impl GlWindow {
         &self.window
     }
 }
+
+fn physical_size(size: Vector2F) -> PhysicalSize<f64> {
+    PhysicalSize::new(size.x() as f64, size.y() as f64)
+}
+
+// let `f` render into `framebuffer` instead of the window of `window_size`
+fn draw_offscreen(renderer: &mut Renderer<GLDevice>, framebuffer: GLFramebuffer, window_size: Vector2I, f: impl FnOnce(&mut Renderer<GLDevice>)) -> GLFramebuffer {
+    renderer.options_mut().dest = DestFramebuffer::Other(framebuffer);
+    f(renderer);
+    match std::mem::replace(&mut renderer.options_mut().dest, DestFramebuffer::full_window(window_size)) {
+        DestFramebuffer::Other(framebuffer) => framebuffer,
+        _ => unreachable!()
+    }
+}
+
+// blit the window framebuffer into a temporary single sampled one of `format` and pass that to `f`.
+// reading it back as RGBA bytes works for both RGBA8 and RGB10_A2.
+fn resolve_multisampled<R>(size: Vector2I, format: gl::types::GLenum, f: impl FnOnce(gl::types::GLuint) -> R) -> R {
+    let (mut framebuffer, mut renderbuffer) = (0, 0);
+    unsafe {
+        gl::GenRenderbuffers(1, &mut renderbuffer);
+        gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+        gl::RenderbufferStorage(gl::RENDERBUFFER, format, size.x(), size.y());
+        gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+
+        gl::GenFramebuffers(1, &mut framebuffer);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+        gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::RENDERBUFFER, renderbuffer);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+        let rect = RectI::new(Vector2I::default(), size);
+        blit(0, framebuffer, rect, rect);
+    }
+    let result = f(framebuffer);
+    unsafe {
+        gl::DeleteFramebuffers(1, &framebuffer);
+        gl::DeleteRenderbuffers(1, &renderbuffer);
+    }
+    result
+}
+
+// read the framebuffer of `size` and keep the top left `visible` part
+fn read_pixels(framebuffer: gl::types::GLuint, size: Vector2I, visible: Vector2I) -> RawImage {
+    let format = PixelFormat::Rgba8Premultiplied;
+    let stride = size.x() as u32 * format.bytes_per_pixel();
+    let mut data = vec![0u8; stride as usize * size.y() as usize];
+    unsafe {
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer);
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(0, 0, size.x(), size.y(), gl::RGBA, gl::UNSIGNED_BYTE, data.as_mut_ptr().cast());
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+    }
+
+    // GL rows are bottom to top
+    let data: Vec<u8> = data.chunks(stride as usize).rev().flatten().cloned().collect();
+
+    RawImage {
+        data,
+        width: visible.x().clamp(0, size.x()) as u32,
+        height: visible.y().clamp(0, size.y()) as u32,
+        stride,
+        format,
+    }
+}
+
+unsafe fn blit(src: gl::types::GLuint, dst: gl::types::GLuint, src_rect: RectI, dst_rect: RectI) {
+    gl::BindFramebuffer(gl::READ_FRAMEBUFFER, src);
+    gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, dst);
+    gl::BlitFramebuffer(
+        src_rect.min_x(), src_rect.min_y(), src_rect.max_x(), src_rect.max_y(),
+        dst_rect.min_x(), dst_rect.min_y(), dst_rect.max_x(), dst_rect.max_y(),
+        gl::COLOR_BUFFER_BIT, gl::NEAREST
+    );
+    gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+}
+
+fn gl_string(name: gl::types::GLenum) -> String {
+    unsafe {
+        let ptr = gl::GetString(name);
+        if ptr.is_null() {
+            return String::new();
+        }
+        CStr::from_ptr(ptr.cast()).to_string_lossy().into_owned()
+    }
+}
+
+fn install_debug_callback() {
+    if !gl::DebugMessageCallback::is_loaded() {
+        warn!("GL debug output is not available (needs KHR_debug)");
+        return;
+    }
+    unsafe {
+        gl::Enable(gl::DEBUG_OUTPUT);
+        gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+        gl::DebugMessageCallback(Some(debug_callback), std::ptr::null());
+    }
+}
+
+extern "system" fn debug_callback(
+    _source: gl::types::GLenum,
+    _gltype: gl::types::GLenum,
+    id: gl::types::GLuint,
+    severity: gl::types::GLenum,
+    length: gl::types::GLsizei,
+    message: *const gl::types::GLchar,
+    _user_param: *mut std::ffi::c_void,
+) {
+    let message = unsafe { std::slice::from_raw_parts(message.cast::<u8>(), length as usize) };
+    let message = String::from_utf8_lossy(message);
+    match severity {
+        gl::DEBUG_SEVERITY_HIGH => error!("GL {}: {}", id, message),
+        gl::DEBUG_SEVERITY_MEDIUM => warn!("GL {}: {}", id, message),
+        gl::DEBUG_SEVERITY_LOW => info!("GL {}: {}", id, message),
+        _ => debug!("GL {}: {}", id, message),
+    }
+}
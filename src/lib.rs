@@ -2,6 +2,7 @@
 pub mod view;
 
 pub use view::Interactive;
+use view::{Theme, KeyCode, CursorGrabMode};
 
 #[cfg(unix)]
 pub mod gl;
@@ -9,6 +10,15 @@ pub mod gl;
 #[cfg(unix)]
 mod show;
 
+#[cfg(unix)]
+mod post;
+
+#[cfg(unix)]
+mod cache;
+
+#[cfg(all(unix, feature="gamepad"))]
+mod gamepad;
+
 #[cfg(unix)]
 pub use show::*;
 
@@ -19,18 +29,26 @@ pub mod wasm;
 pub use wasm::*;
 
 mod util;
+pub use util::{round_v_to_tile, tile_size};
+
+#[cfg(feature="pdf")]
+mod pdf;
 
 use pathfinder_geometry::{
-    vector::{Vector2F},
+    vector::{Vector2F, Vector2I},
     rect::RectF,
     transform2d::Transform2F,
 };
-use pathfinder_color::ColorF;
+use pathfinder_color::{ColorF, ColorU};
 use pathfinder_renderer::{
     scene::Scene,
-    gpu::options::RendererLevel
+    gpu::options::RendererLevel,
+    options::{BuildOptions, RenderTransform},
 };
 use pathfinder_resources::{ResourceLoader};
+use std::time::Duration;
+use std::any::Any;
+use std::collections::HashMap;
 
 
 pub struct Config {
@@ -42,6 +60,103 @@ pub struct Config {
     pub render_level: RendererLevel,
     pub resource_loader: Box<dyn ResourceLoader>,
     pub threads: bool,
+    pub invert: bool,
+    pub min_window_size: Option<Vector2F>,
+    pub max_window_size_limit: Option<Vector2F>,
+    pub zoom_step: f32, // log2 of the zoom factor per key press
+    pub zoom_focus: ZoomFocus, // the point that stays in place when zooming with the wheel
+    // render large scenes in bands over several frames. the post process effect is applied,
+    // split viewports are still drawn in one frame.
+    pub progressive: bool,
+    pub pixel_snap: bool, // align to device pixels when the zoom is a whole ratio
+    pub thumbnail_size: Vector2I,
+    // thumbnails are rendered this many times larger and scaled down, so small text does not alias
+    pub thumbnail_supersample: u32,
+    pub post_process: Option<PostProcess>,
+    pub start_maximized: bool,
+    pub fit_mode: FitMode,
+    pub vsync: bool,
+    #[cfg(feature="png")]
+    pub one_shot: Option<OneShot>,
+    pub loop_mode: LoopMode,
+    pub gl_debug: bool, // log messages from the GL driver
+    pub copy_image_key: Option<KeyCode>, // with Ctrl+Shift, copies the view to the clipboard
+    pub pan_axes: (bool, bool), // panning allowed along (x, y)
+    pub zoom_wheel_gain: f32, // log2 zoom per logical pixel of ctrl+wheel
+    pub zoom_wheel_acceleration: f32, // extra gain for fast scrolling, 0 is linear
+    pub invert_zoom_wheel: bool, // flip the direction of ctrl+wheel zoom
+    // prefer a hardware accelerated config, software ones are still used if nothing else matches.
+    // GL can not pick the GPU, on multi-GPU Linux systems use DRI_PRIME=1 (Mesa)
+    // or __NV_PRIME_RENDER_OFFLOAD=1 (Nvidia) to select the discrete one. `Context::gl_config_info` reports what was used.
+    pub prefer_hardware: bool,
+    pub redraw_on_focus: bool,
+    pub grid: Option<GridSpec>,
+    // draw a light/dark checkerboard behind the scene so transparent parts are visible.
+    // the squares have a fixed size in pixels, independent of the zoom.
+    pub transparency_checkerboard: bool,
+    // dragging with the left button selects text, see `Interactive::selection_changed`.
+    // panning by mouse then needs shift.
+    pub text_selection: bool,
+    // use the right button for panning with shift and selecting, for left-handed use.
+    // Windows, X11 and browsers already report buttons swapped in the system settings as swapped.
+    pub swap_mouse_buttons: bool,
+    // framebuffers are padded to the tile size. by default the scene view box covers the padding,
+    // with `exact_size` it is the requested size, so rendered images match it pixel for pixel.
+    pub exact_size: bool,
+    pub debug_ui: bool, // pathfinder's stats overlay
+    pub debug_ui_key: Option<KeyCode>, // toggles the debug UI, i.e. Some(KeyCode::F12)
+    // bytes of rendered frames to keep for going back and forth between pages, 0 disables the cache.
+    // frames are reused for the same page, zoom, position, background, theme and post process effect.
+    // a reused frame skips `Interactive::scene`, so if the scene of a page can change otherwise,
+    // call `Context::invalidate_render_cache` when it does.
+    pub render_cache_budget: usize,
+    pub title: Option<String>, // window title, instead of `Interactive::title`
+    // width / height of the shown area, the rest of the window is filled with `background`
+    pub aspect_ratio: Option<f32>,
+    // clear to `background` before each frame. without it, transparent parts of the scene show
+    // what was in the framebuffer before, for overlays. what that is depends on how the platform swaps buffers.
+    pub clear: bool,
+    // identical output for identical input, i.e. for image-diff tests.
+    // builds scenes on one thread and uses the D3D9 renderer, `threads` and `render_level` are ignored.
+    pub deterministic: bool,
+    // add up fractional line scrolling, see `Context::scroll_lines`
+    pub accumulate_line_scroll: bool,
+    // when the user resizes the window, change the other dimension to keep the aspect ratio
+    // of `aspect_ratio`, or of the bounds without it
+    pub lock_aspect: bool,
+    // fit the view to the actual window size before the first frame,
+    // instead of the requested one and again once the window reports its size.
+    // with `FitMode::Free` the whole page is fit once, later resizes keep the scale.
+    pub fit_on_first_frame: bool,
+    // the point of the window that keeps showing the same part of the scene while resizing
+    pub resize_anchor: Anchor,
+    pub idle_policy: IdlePolicy,
+    // application id (Wayland) and WM_CLASS (X11), for taskbar grouping and icons. ignored elsewhere.
+    pub app_id: Option<String>,
+    pub text_aa: TextAaMode,
+    // keep running when the item panics: the panic is logged, the event dropped
+    // and an error scene shown in place of a scene that could not be built
+    pub catch_panics: bool,
+    // what `goto_page`, `next_page` and `prev_page` do past the first or last page
+    pub page_wrap: PageWrap,
+    // convert captured pixels to straight alpha, see `RawImage::unmultiply`
+    pub unmultiply: bool,
+    // with `progressive`, keep the previous frame on screen until all bands of the next one are done,
+    // instead of showing it band by band over the background. other frames are always presented whole.
+    pub hold_frame: bool,
+    // bits per channel of the window surface. native only, the browser picks the canvas format.
+    pub color_depth: ColorDepth,
+    // holding ctrl+1 or ctrl+2 zooms continuously with the frame rate until the key is released,
+    // instead of a `zoom_step` at each key repeat
+    pub smooth_key_zoom: bool,
+    // while the window is resized, render at most once per interval instead of for every new size
+    pub resize_frame_interval: Option<Duration>,
+    // keep the cursor in the window (`Confined`) or in place (`Locked`) from the start,
+    // see `Context::set_cursor_grab`
+    pub cursor_grab: CursorGrabMode,
+    // handle only the last of several cursor moves or resizes that arrive together,
+    // i.e. from a fast trackpad. they are applied before any other event, so the order is kept.
+    pub coalesce_events: bool,
 }
 impl Config {
     pub fn new(resource_loader: Box<dyn ResourceLoader>) -> Self {
@@ -54,10 +169,174 @@ impl Config {
             render_level: RendererLevel::D3D9,
             resource_loader,
             threads: true,
+            invert: false,
+            min_window_size: None,
+            max_window_size_limit: None,
+            zoom_step: 0.2,
+            zoom_focus: ZoomFocus::ViewCenter,
+            progressive: false,
+            pixel_snap: false,
+            thumbnail_size: Vector2I::new(128, 128),
+            thumbnail_supersample: 2,
+            post_process: None,
+            start_maximized: false,
+            fit_mode: FitMode::Free,
+            vsync: true,
+            #[cfg(feature="png")]
+            one_shot: None,
+            loop_mode: LoopMode::Wait,
+            gl_debug: false,
+            copy_image_key: Some(KeyCode::KeyC),
+            pan_axes: (true, true),
+            zoom_wheel_gain: 0.02,
+            zoom_wheel_acceleration: 0.0,
+            invert_zoom_wheel: false,
+            prefer_hardware: true,
+            redraw_on_focus: true,
+            grid: None,
+            transparency_checkerboard: false,
+            text_selection: false,
+            swap_mouse_buttons: false,
+            exact_size: false,
+            debug_ui: false,
+            debug_ui_key: None,
+            render_cache_budget: 0,
+            title: None,
+            aspect_ratio: None,
+            clear: true,
+            deterministic: false,
+            accumulate_line_scroll: true,
+            lock_aspect: false,
+            fit_on_first_frame: false,
+            resize_anchor: Anchor::Center,
+            idle_policy: IdlePolicy::EveryLoop,
+            app_id: None,
+            text_aa: TextAaMode::Grayscale,
+            catch_panics: false,
+            page_wrap: PageWrap::Clamp,
+            unmultiply: false,
+            hold_frame: false,
+            color_depth: ColorDepth::Rgba8,
+            smooth_key_zoom: false,
+            resize_frame_interval: None,
+            cursor_grab: CursorGrabMode::None,
+            coalesce_events: false,
+        }
+    }
+}
+
+// render a single frame to `output` as PNG and exit
+#[cfg(feature="png")]
+#[derive(Clone, Debug)]
+pub struct OneShot {
+    pub page: usize,
+    pub scale: f32,
+    pub output: std::path::PathBuf,
+}
+
+// how the event loop waits when there is nothing to do.
+// `Context::update_interval` and redraw requests still wake it up in every mode.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LoopMode {
+    Wait,                   // sleep until the next event. no CPU usage while idle.
+    Poll,                   // never sleep. keeps a core busy, for polling devices in `idle`.
+    WaitUntil(Duration),    // wake up at least this often to call `idle`, without redrawing.
+}
+
+// when `Interactive::idle` is called
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IdlePolicy {
+    EveryLoop,  // each time the event loop ran out of events, which can be very often
+    PerFrame,   // once after each rendered frame
+    Never,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorDepth {
+    Rgba8,      // 8 bits for each color and alpha
+    Rgb10A2,    // 10 bits per color and 2 for alpha, for 10 bit displays
+}
+impl ColorDepth {
+    // (color bits, alpha bits)
+    pub fn bits(self) -> (u8, u8) {
+        match self {
+            ColorDepth::Rgba8 => (8, 8),
+            ColorDepth::Rgb10A2 => (10, 2),
         }
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ZoomFocus {
+    Center,     // the middle of the window
+    Cursor,     // the scene point under the mouse
+    ViewCenter, // the middle of the active view, differs from `Center` with viewports or `aspect_ratio`
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PageWrap {
+    Clamp,  // stay on the first or last page
+    Wrap,   // continue at the other end, for slideshows
+    Ignore, // do not change the page at all
+}
+
+// antialiasing of the whole scene, pathfinder does not treat text differently.
+// it always antialiases, so there is no mode without it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TextAaMode {
+    Grayscale,  // coverage only
+    // every outline, not only text, is grown by a fixed ~0.2px in window pixels at any zoom.
+    // thin strokes and small text get bolder, larger shapes barely change.
+    Darkened,
+}
+impl TextAaMode {
+    pub (crate) fn build_options(self, transform: RenderTransform) -> BuildOptions {
+        // the dilation pathfinder's demo uses for stem darkening
+        const STEM_DARKENING: (f32, f32) = (0.0121 * 16.0, 0.0121 * 1.25 * 16.0);
+        let dilation = match self {
+            TextAaMode::Grayscale => Vector2F::default(),
+            TextAaMode::Darkened => Vector2F::new(STEM_DARKENING.0, STEM_DARKENING.1),
+        };
+        // subpixel AA renders 3x as wide and needs a `Filter::Text` composite, which is not set up
+        BuildOptions { transform, dilation, subpixel_aa_enabled: false }
+    }
+}
+
+// how the scale follows the window size
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FitMode {
+    Free,       // the scale is only changed by zooming
+    FitWidth,   // the width of the bounds fills the window
+    FitPage,    // the bounds fit entirely into the window
+    Actual,     // physical size, see `Context::actual_size_scale`
+}
+
+// grid lines every `spacing` scene units, with `subdivisions` fainter lines in between,
+// and rulers along the top and left edge of the window
+#[derive(Copy, Clone, Debug)]
+pub struct GridSpec {
+    pub spacing: f32,
+    pub subdivisions: u32,
+    pub color: ColorU,
+}
+
+// sides of the bounds
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+// effects applied to the rendered frame
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PostProcess {
+    Grayscale,
+    Sepia,
+    EdgeDetect,
+}
+
 pub struct Icon {
     data: Vec<u8>,
     width: u32,
@@ -74,6 +353,130 @@ impl From<image::RgbaImage> for Icon {
     }
 }
 
+// wall clock time passed to `Interactive::tick`.
+// on the web it is the `performance.now()` of the page, in milliseconds.
+#[cfg(not(target_arch="wasm32"))]
+pub type Timestamp = std::time::Instant;
+#[cfg(target_arch="wasm32")]
+pub type Timestamp = f64;
+
+// why the viewer could not start, see `Interactive::on_init_error`
+#[derive(Debug)]
+pub enum InitError {
+    // the `ResourceLoader` could not provide a file pathfinder needs
+    MissingResource { path: String, error: std::io::Error },
+}
+impl std::fmt::Display for InitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InitError::MissingResource { path, error } => write!(f, "the resource loader can not provide {:?}: {}", path, error),
+        }
+    }
+}
+impl std::error::Error for InitError {}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RenderCacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub budget: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    Rgba8,              // straight alpha, what PNG and most image APIs expect
+    Rgba8Premultiplied, // color already multiplied by alpha, what the renderer produces
+}
+impl PixelFormat {
+    pub fn bytes_per_pixel(self) -> u32 {
+        match self {
+            PixelFormat::Rgba8 | PixelFormat::Rgba8Premultiplied => 4,
+        }
+    }
+}
+
+/// pixel data read back from the renderer.
+/// rows are stored top to bottom, `stride` bytes apart.
+/// `stride` can be larger than `width * bytes_per_pixel` when the framebuffer was padded.
+#[derive(Clone)]
+pub struct RawImage {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub stride: u32,
+    pub format: PixelFormat,
+}
+impl RawImage {
+    pub fn row(&self, y: u32) -> &[u8] {
+        let start = (y * self.stride) as usize;
+        &self.data[start .. start + (self.width * self.format.bytes_per_pixel()) as usize]
+    }
+
+    // convert premultiplied to straight alpha. without this, transparent edges come out too dark.
+    pub fn unmultiply(&mut self) {
+        if self.format != PixelFormat::Rgba8Premultiplied {
+            return;
+        }
+        for px in self.data.chunks_exact_mut(4) {
+            let a = px[3] as u32;
+            if a != 0 && a != 255 {
+                for c in &mut px[.. 3] {
+                    *c = ((*c as u32 * 255 + a / 2) / a).min(255) as u8;
+                }
+            }
+        }
+        self.format = PixelFormat::Rgba8;
+    }
+
+    // average each `factor` x `factor` block into one pixel, for images rendered at a multiple of the wanted size
+    pub fn downsample(&self, factor: u32) -> RawImage {
+        if factor <= 1 {
+            return self.clone();
+        }
+        let bpp = self.format.bytes_per_pixel();
+        let (width, height) = (self.width / factor, self.height / factor);
+        let stride = width * bpp;
+        let mut data = Vec::with_capacity((stride * height) as usize);
+        for y in 0 .. height {
+            let rows: Vec<&[u8]> = (0 .. factor).map(|dy| self.row(y * factor + dy)).collect();
+            for x in 0 .. width {
+                for c in 0 .. bpp {
+                    let mut sum = 0;
+                    for row in &rows {
+                        for dx in 0 .. factor {
+                            sum += row[((x * factor + dx) * bpp + c) as usize] as u32;
+                        }
+                    }
+                    data.push((sum / (factor * factor)) as u8);
+                }
+            }
+        }
+        RawImage { data, width, height, stride, format: self.format }
+    }
+
+    #[cfg(feature="png")]
+    pub fn write_png(&self, writer: impl std::io::Write) -> Result<(), png::EncodingError> {
+        if self.format == PixelFormat::Rgba8Premultiplied {
+            let mut image = self.clone();
+            image.unmultiply();
+            return image.write_png(writer);
+        }
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        let data: Vec<u8> = (0 .. self.height).flat_map(|y| self.row(y).iter().cloned()).collect();
+        writer.write_image_data(&data)
+    }
+    #[cfg(feature="png")]
+    pub fn save_png(&self, path: impl AsRef<std::path::Path>) -> Result<(), png::EncodingError> {
+        let file = std::fs::File::create(path)?;
+        self.write_png(std::io::BufWriter::new(file))
+    }
+}
+
 pub struct Context {
     // - the window needs a repaint
     pub (crate) redraw_requested: bool,
@@ -87,11 +490,95 @@ pub struct Context {
     pub (crate) bounds: Option<RectF>,
     pub (crate) close: bool,
     pub update_interval: Option<f32>,
+    pub (crate) max_fps: Option<f32>,
+    pub (crate) queued_events: Vec<Box<dyn Any>>,
+    pub (crate) theme: Option<Theme>,
+    pub (crate) thumbnails: HashMap<(usize, (i32, i32)), RawImage>,
+    pub (crate) pending_thumbnails: Vec<usize>,
+    pub (crate) dragging: bool,
+    pub (crate) pointer_down: bool, // the primary button is held outside of panning
+    pub (crate) reached_edges: Vec<Edge>, // not yet passed to `edge_reached`
+    at_edges: Vec<Edge>, // where the view is now, so an edge is only reported when it is reached
+    pub (crate) presentation: Option<Presentation>,
+    pub (crate) cursor_hidden: bool,
+    pub (crate) page_change: Option<(usize, usize)>,
+    pub (crate) page_rotation: HashMap<usize, u8>, // quarter turns clockwise, only pages that are rotated
+    pub (crate) selection_range: Option<(Vector2F, Vector2F)>,
+    pub (crate) selecting: bool,
+    pub (crate) selection_changed: bool,
+    pub (crate) text_selection: Vec<RectF>,
+    pub (crate) cursor_pos: Option<Vector2F>,
+    pub (crate) viewports: Vec<ViewPort>,
+    pub (crate) active_viewport: usize,
+    pub (crate) linked_viewports: bool,
+    pub (crate) window_opacity: f32,
+    pub (crate) recording: Option<(Duration, ViewTrack)>,
+    pub (crate) frame_rendered: bool, // since the last call of `idle`
+    pub (crate) playback: Option<(Duration, ViewTrack)>,
+    pub (crate) pending_cursor: Option<Vector2F>, // held back by `Config::coalesce_events`
+    pub (crate) pending_resize: Option<Vector2F>,
+    pub (crate) minimized: bool, // the window has no area, nothing is rendered
+    pub (crate) transform_override: Option<Transform2F>, // see `render_with_transform`
+    pub (crate) document_dpi: f32, // scene units per inch
+    pub (crate) redraw_at: Option<Duration>, // backend time of a `request_redraw_after`
+    pub (crate) page_scenes: HashMap<usize, (Scene, Transform2F)>, // with the view they were built for, see `set_page_scene`
     pub pixel_scroll_factor: Vector2F,
     pub line_scroll_factor: Vector2F,
+    line_scroll_remainder: Vector2F,
     backend: Backend,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    Center,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+impl Anchor {
+    // position within the window, (0, 0) is the top left corner and (1, 1) the bottom right one
+    pub fn fraction(self) -> Vector2F {
+        match self {
+            Anchor::Center => Vector2F::splat(0.5),
+            Anchor::TopLeft => Vector2F::new(0.0, 0.0),
+            Anchor::TopRight => Vector2F::new(1.0, 0.0),
+            Anchor::BottomLeft => Vector2F::new(0.0, 1.0),
+            Anchor::BottomRight => Vector2F::new(1.0, 1.0),
+        }
+    }
+}
+
+// pan and zoom over time, see `Context::start_recording`
+#[derive(Clone, Debug, Default)]
+pub struct ViewTrack {
+    pub samples: Vec<ViewSample>,
+}
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ViewSample {
+    pub time: Duration, // since the start of the track
+    pub scale: f32,
+    pub view_center: Vector2F,
+}
+
+// one region of a split window with its own view
+#[derive(Copy, Clone, Debug)]
+pub struct ViewPort {
+    pub rect: RectF, // fraction of the window, (0, 0) to (1, 1) is all of it
+    pub view_center: Vector2F,
+    pub scale: f32,
+    pub page: usize,
+}
+
+// settings replaced by the presentation mode
+pub (crate) struct Presentation {
+    pan: bool,
+    zoom: bool,
+}
+
+// hide the cursor after this long without movement in presentation mode
+pub const PRESENTATION_CURSOR_TIMEOUT: Duration = Duration::from_secs(2);
+
 pub const DEFAULT_SCALE: f32 = 96.0 / 25.4;
 impl Context {
     pub fn new(config: Config, backend: Backend) -> Self {
@@ -108,35 +595,186 @@ impl Context {
             bounds: None,
             close: false,
             update_interval: None,
+            max_fps: None,
+            queued_events: vec![],
+            theme: None,
+            thumbnails: HashMap::new(),
+            pending_thumbnails: vec![],
+            dragging: false,
+            pointer_down: false,
+            reached_edges: vec![],
+            at_edges: vec![],
+            presentation: None,
+            cursor_hidden: false,
+            page_change: None,
+            page_rotation: HashMap::new(),
+            selection_range: None,
+            selecting: false,
+            selection_changed: false,
+            text_selection: vec![],
+            cursor_pos: None,
+            viewports: vec![],
+            active_viewport: 0,
+            linked_viewports: false,
+            window_opacity: 1.0,
+            recording: None,
+            frame_rendered: false,
+            playback: None,
+            pending_cursor: None,
+            pending_resize: None,
+            minimized: false,
+            transform_override: None,
+            document_dpi: 25.4,
+            redraw_at: None,
+            page_scenes: HashMap::new(),
             pixel_scroll_factor,
             line_scroll_factor,
+            line_scroll_remainder: Vector2F::default(),
             backend,
         }
     }
+    // any number of requests before the next frame result in a single render
     pub fn request_redraw(&mut self) {
         self.redraw_requested = true;
     }
+    // redraw once after `dt`, i.e. to hide something shown for a while.
+    // unlike `update_interval` this does not repeat. an earlier pending request wins.
+    pub fn request_redraw_after(&mut self, dt: Duration) {
+        let t = self.backend.time() + dt;
+        self.redraw_at = Some(self.redraw_at.map_or(t, |t0| t0.min(t)));
+    }
+    // requests the redraw if it is due, otherwise returns how long until it is
+    pub (crate) fn check_redraw_timer(&mut self) -> Option<Duration> {
+        let t = self.redraw_at?;
+        let now = self.backend.time();
+        if now >= t {
+            self.redraw_at = None;
+            self.request_redraw();
+            None
+        } else {
+            Some(t - now)
+        }
+    }
+    pub fn needs_redraw(&self) -> bool {
+        self.redraw_requested
+    }
+    // for loops driven from the outside: call once per frame and render if it returns true
+    pub fn take_redraw_request(&mut self) -> bool {
+        std::mem::replace(&mut self.redraw_requested, false)
+    }
+    // a page past the last one is handled according to `Config::page_wrap`
     pub fn goto_page(&mut self, page: usize) {
-        let page = page.min(self.num_pages - 1);
+        let last = self.num_pages.saturating_sub(1);
+        let page = match self.config.page_wrap {
+            _ if page <= last => page,
+            PageWrap::Clamp => last,
+            PageWrap::Wrap => page % self.num_pages.max(1),
+            PageWrap::Ignore => return,
+        };
+        self.show_page(page);
+    }
+    fn show_page(&mut self, page: usize) {
         if page != self.page_nr {
+            let old = self.page_change.map_or(self.page_nr, |(old, _)| old);
+            let rotation_changed = self.page_rotation(page) != self.page_rotation(self.page_nr);
+            self.page_change = Some((old, page));
             self.page_nr = page;
+            if rotation_changed {
+                self.apply_fit_mode();
+            }
             self.request_redraw();
         }
     }
+    // show `page` turned by `quarter_turns` * 90° clockwise, i.e. for pages scanned sideways.
+    // negative values turn counter-clockwise.
+    pub fn set_page_rotation(&mut self, page: usize, quarter_turns: i32) {
+        let turns = quarter_turns.rem_euclid(4) as u8;
+        let old = if turns == 0 {
+            self.page_rotation.remove(&page)
+        } else {
+            self.page_rotation.insert(page, turns)
+        };
+        if old.unwrap_or(0) != turns {
+            self.invalidate_render_cache();
+            if page == self.page_nr {
+                self.apply_fit_mode();
+                self.request_redraw();
+            }
+        }
+    }
+    pub fn page_rotation(&self, page: usize) -> u8 {
+        self.page_rotation.get(&page).copied().unwrap_or(0)
+    }
+    // turns the current page about the center of the bounds.
+    // part of `view_transform`, so scene coordinates stay those of the unrotated page.
+    pub fn page_transform(&self) -> Transform2F {
+        match self.page_rotation(self.page_nr) {
+            0 => Transform2F::default(),
+            turns => {
+                let center = self.bounds.map_or(Vector2F::default(), |b| b.center());
+                Transform2F::from_translation(center) *
+                    Transform2F::from_rotation(turns as f32 * std::f32::consts::FRAC_PI_2) *
+                    Transform2F::from_translation(-center)
+            }
+        }
+    }
+    // the bounds as they are shown, with the rotation of the current page
+    fn visible_bounds(&self) -> Option<RectF> {
+        let bounds = self.bounds?;
+        if self.page_rotation(self.page_nr) % 2 == 0 {
+            return Some(bounds);
+        }
+        let size = Vector2F::new(bounds.height(), bounds.width());
+        Some(RectF::new(bounds.center() - size * 0.5, size))
+    }
+    pub fn set_page_count(&mut self, num_pages: usize) {
+        self.num_pages = num_pages;
+        self.show_page(self.page_nr.min(num_pages.saturating_sub(1)));
+    }
     pub fn next_page(&mut self) {
         self.goto_page(self.page_nr.saturating_add(1));
     }
     pub fn prev_page(&mut self) {
-        self.goto_page(self.page_nr.saturating_sub(1));
+        match (self.page_nr, self.config.page_wrap) {
+            (0, PageWrap::Wrap) => self.goto_page(self.num_pages.saturating_sub(1)),
+            (0, _) => {}
+            (n, _) => self.goto_page(n - 1),
+        }
     }
     pub fn page_nr(&self) -> usize {
         self.page_nr
     }
+    // `zoom_by` and `set_zoom` keep `view_center`, i.e. the middle of the window, in place.
+    // `zoom_at` and `zoom_keeping_window_point` keep the scene point under a given window pixel in place,
+    // which is what a zoom following the mouse wants.
+    // either way, an axis on which the scene is smaller than the window is centered again by the bounds.
     pub fn zoom_by(&mut self, log2_factor: f32) {
         self.scale *= 2f32.powf(log2_factor);
         self.check_bounds();
         self.request_redraw();
     }
+    // zoom while keeping the scene point under `window_point` (in pixels) in place
+    pub fn zoom_at(&mut self, log2_factor: f32, window_point: Vector2F) {
+        self.zoom_keeping_window_point(self.scale * 2f32.powf(log2_factor), window_point);
+    }
+    // zoom about the point `Config::zoom_focus` asks for
+    pub fn zoom_focused(&mut self, log2_factor: f32) {
+        let point = match self.config.zoom_focus {
+            ZoomFocus::Center => self.window_size * 0.5,
+            ZoomFocus::Cursor => self.cursor_pos.unwrap_or(self.viewport().center()),
+            ZoomFocus::ViewCenter => self.viewport().center(),
+        };
+        self.zoom_at(log2_factor, point);
+    }
+    // set the scale to `scale` while keeping the scene point under `window_point` (in pixels) in place
+    pub fn zoom_keeping_window_point(&mut self, scale: f32, window_point: Vector2F) {
+        let offset = window_point - self.viewport().center();
+        let scene_point = self.view_center + offset * (1.0 / self.scale);
+        self.scale = scale;
+        self.view_center = scene_point - offset * (1.0 / self.scale);
+        self.check_bounds();
+        self.request_redraw();
+    }
     pub fn set_zoom(&mut self, factor: f32) {
         if factor != self.scale {
             self.scale = factor;
@@ -146,6 +784,327 @@ impl Context {
     }
 
 
+    // limit how often a frame is rendered, both for `update_interval` and redraw requests.
+    pub fn set_max_fps(&mut self, fps: Option<f32>) {
+        self.max_fps = fps.filter(|&fps| fps > 0.0);
+    }
+    pub fn max_fps(&self) -> Option<f32> {
+        self.max_fps
+    }
+    pub (crate) fn min_frame_duration(&self) -> Option<Duration> {
+        self.max_fps.map(|fps| Duration::from_secs_f32(1.0 / fps))
+    }
+
+    // deliver `event` to `Interactive::event` on the next idle pass.
+    // other types than the `Event` of the item go to `Interactive::queued_event`.
+    pub fn queue_event<E: Any>(&mut self, event: E) {
+        self.queued_events.push(Box::new(event));
+    }
+    // deliver the queued events, those of another type go to `Interactive::queued_event`
+    pub (crate) fn dispatch_queued_events<T: Interactive + ?Sized>(&mut self, item: &mut T) {
+        for event in std::mem::take(&mut self.queued_events) {
+            match event.downcast::<T::Event>() {
+                Ok(event) => item.event(self, *event),
+                Err(event) => item.queued_event(self, event),
+            }
+        }
+    }
+
+    // fit the bounds into the window, or go back to the default scale without bounds
+    pub fn reset_view(&mut self) {
+        match (self.fit_scale(FitMode::FitPage), self.visible_bounds()) {
+            (Some(scale), Some(bounds)) => {
+                self.scale = scale;
+                self.view_center = bounds.center();
+            }
+            _ => {
+                self.scale = DEFAULT_SCALE;
+                self.view_center = Vector2F::default();
+            }
+        }
+        self.check_bounds();
+        self.request_redraw();
+    }
+
+    pub fn set_fit_mode(&mut self, mode: FitMode) {
+        self.config.fit_mode = mode;
+        self.apply_fit_mode();
+        self.request_redraw();
+    }
+    pub fn fit_mode(&self) -> FitMode {
+        self.config.fit_mode
+    }
+    // the scale `mode` asks for at the current window size, None if it does not apply
+    fn fit_scale(&self, mode: FitMode) -> Option<f32> {
+        let bounds = self.visible_bounds().filter(|b| b.width() > 0.0 && b.height() > 0.0);
+        match mode {
+            FitMode::Free => None,
+            FitMode::Actual => Some(self.actual_size_scale()),
+            FitMode::FitWidth => bounds.map(|b| self.viewport().width() / b.width()),
+            FitMode::FitPage => bounds.map(|b| {
+                let s = self.viewport().size() * b.size().recip();
+                s.x().min(s.y())
+            }),
+        }
+    }
+    pub (crate) fn apply_fit_mode(&mut self) {
+        if let Some(scale) = self.fit_scale(self.config.fit_mode) {
+            self.scale = scale;
+        }
+        self.check_bounds();
+    }
+    // see `Config::fit_on_first_frame`
+    pub (crate) fn fit_first_frame(&mut self) {
+        let mode = match self.config.fit_mode {
+            FitMode::Free => FitMode::FitPage,
+            mode => mode,
+        };
+        if let Some(scale) = self.fit_scale(mode) {
+            self.scale = scale;
+        }
+        self.check_bounds();
+    }
+
+    // wheel input in logical pixels, so scrolling feels the same on every display.
+    // one line moves by `line_scroll_factor` logical pixels, with the default of 10 a wheel notch
+    // pans by `10 * scale_factor / scale` scene units.
+    // with `Config::accumulate_line_scroll`, the movement is given in whole pixels and the
+    // rest is kept for the next call, so the many fractional lines of high resolution wheels add up
+    // smoothly instead of each one being rounded or amplified.
+    pub fn scroll_lines(&mut self, lines: Vector2F) -> Vector2F {
+        let delta = lines * self.line_scroll_factor;
+        if !self.config.accumulate_line_scroll {
+            return delta;
+        }
+        // a change of direction drops what was left over
+        let mut remainder = self.line_scroll_remainder;
+        if remainder.x() * delta.x() < 0.0 {
+            remainder.set_x(0.0);
+        }
+        if remainder.y() * delta.y() < 0.0 {
+            remainder.set_y(0.0);
+        }
+        let total = remainder + delta;
+        let whole = Vector2F::new(total.x().trunc(), total.y().trunc());
+        self.line_scroll_remainder = total - whole;
+        whole
+    }
+    // `pixels` are device pixels
+    pub fn scroll_pixels(&self, pixels: Vector2F) -> Vector2F {
+        pixels * self.pixel_scroll_factor * (1.0 / self.scale_factor)
+    }
+
+    // left, top, width and height in CSS pixels of `rect` (in scene units) on screen,
+    // for placing HTML elements over the scene. native windows report logical pixels.
+    pub fn scene_to_screen_rect(&self, rect: RectF) -> (f64, f64, f64, f64) {
+        // the web backend keeps the window size in CSS pixels already
+        let css_per_pixel = if cfg!(target_arch="wasm32") { 1.0 } else { 1.0 / self.scale_factor };
+        let r = (self.view_transform() * rect) * css_per_pixel;
+        (r.min_x() as f64, r.min_y() as f64, r.width() as f64, r.height() as f64)
+    }
+
+    // the cursor position in scene units, None if the cursor is outside the window.
+    // follows pan and zoom, the cursor does not have to move.
+    pub fn cursor_scene_pos(&self) -> Option<Vector2F> {
+        self.cursor_pos.map(|pos| self.view_transform().inverse() * pos)
+    }
+
+    // start and end of the current text selection drag, in scene units.
+    // the item maps these to characters in `Interactive::selection_changed`.
+    pub fn selection_range(&self) -> Option<(Vector2F, Vector2F)> {
+        self.selection_range
+    }
+    // true while the user drags a selection
+    pub fn is_selecting(&self) -> bool {
+        self.selecting
+    }
+    // rectangles (in scene units) to highlight as selected text
+    pub fn set_text_selection(&mut self, rects: Vec<RectF>) {
+        if rects != self.text_selection {
+            self.text_selection = rects;
+            self.invalidate_render_cache();
+            self.request_redraw();
+        }
+    }
+    pub fn text_selection(&self) -> &[RectF] {
+        &self.text_selection
+    }
+    pub fn clear_selection(&mut self) {
+        self.selection_range = None;
+        self.selecting = false;
+        self.set_text_selection(vec![]);
+    }
+    pub (crate) fn begin_selection(&mut self, scene_pos: Vector2F) {
+        self.selection_range = Some((scene_pos, scene_pos));
+        self.selecting = true;
+        self.selection_changed = true;
+    }
+    pub (crate) fn update_selection(&mut self, scene_pos: Vector2F) {
+        if let Some((_, ref mut end)) = self.selection_range {
+            if *end != scene_pos {
+                *end = scene_pos;
+                self.selection_changed = true;
+            }
+        }
+    }
+    pub (crate) fn end_selection(&mut self) {
+        self.selecting = false;
+    }
+
+    // true while the user pans the view with the mouse
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    // fullscreen without pan and zoom, the arrow keys change pages and the cursor hides when idle.
+    pub fn set_presentation_mode(&mut self, on: bool) {
+        match (on, self.presentation.take()) {
+            (true, None) => {
+                self.presentation = Some(Presentation {
+                    pan: self.config.pan,
+                    zoom: self.config.zoom,
+                });
+                self.config.pan = false;
+                self.config.zoom = false;
+                self.backend.set_fullscreen(true);
+            }
+            (false, Some(saved)) => {
+                self.config.pan = saved.pan;
+                self.config.zoom = saved.zoom;
+                self.backend.set_fullscreen(false);
+                self.set_cursor_hidden(false);
+            }
+            (_, saved) => self.presentation = saved,
+        }
+        self.request_redraw();
+    }
+    pub fn presentation_mode(&self) -> bool {
+        self.presentation.is_some()
+    }
+    // not every platform has both modes, the other one is used then. on the web this does nothing,
+    // the page has to call `requestPointerLock` from an input handler.
+    pub fn set_cursor_grab(&mut self, mode: CursorGrabMode) {
+        self.config.cursor_grab = mode;
+        self.backend.set_cursor_grab(mode);
+    }
+    pub fn cursor_grab(&self) -> CursorGrabMode {
+        self.config.cursor_grab
+    }
+    pub (crate) fn set_cursor_hidden(&mut self, hidden: bool) {
+        if hidden != self.cursor_hidden {
+            self.cursor_hidden = hidden;
+            self.backend.set_cursor_visible(!hidden);
+        }
+    }
+
+    pub fn set_loop_mode(&mut self, mode: LoopMode) {
+        self.config.loop_mode = mode;
+    }
+
+    // log2 zoom factor for a wheel delta of `dy` logical pixels.
+    // larger deltas (fast scrolling) are amplified by `zoom_wheel_acceleration`.
+    pub (crate) fn wheel_zoom(&self, dy: f32) -> f32 {
+        let acceleration = 1.0 + self.config.zoom_wheel_acceleration * dy.abs() * 0.1;
+        let direction = if self.config.invert_zoom_wheel { 1.0 } else { -1.0 };
+        direction * self.config.zoom_wheel_gain * dy * acceleration
+    }
+
+    // whether `Interactive::idle` should be called now, see `Config::idle_policy`
+    pub (crate) fn take_idle(&mut self) -> bool {
+        let frame_rendered = std::mem::replace(&mut self.frame_rendered, false);
+        match self.config.idle_policy {
+            IdlePolicy::EveryLoop => true,
+            IdlePolicy::PerFrame => frame_rendered,
+            IdlePolicy::Never => false,
+        }
+    }
+
+    // tell the item about changes that happened while handling events
+    pub (crate) fn dispatch_notifications<T: Interactive + ?Sized>(&mut self, item: &mut T) {
+        if let Some((old, new)) = self.page_change.take() {
+            if old != new {
+                item.page_changed(self, old, new);
+            }
+        }
+        for edge in std::mem::take(&mut self.reached_edges) {
+            item.edge_reached(self, edge);
+        }
+        if std::mem::replace(&mut self.selection_changed, false) {
+            item.selection_changed(self);
+        }
+    }
+
+    // record every change of pan and zoom until `stop_recording`
+    pub fn start_recording(&mut self) {
+        let start = self.backend.time();
+        let mut track = ViewTrack::default();
+        track.samples.push(self.view_sample(Duration::default()));
+        self.recording = Some((start, track));
+    }
+    pub fn stop_recording(&mut self) -> ViewTrack {
+        self.recording.take().map(|(_, track)| track).unwrap_or_default()
+    }
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+    // animate the view through `track`, in real time
+    pub fn play_track(&mut self, track: ViewTrack) {
+        if track.samples.is_empty() {
+            return;
+        }
+        self.playback = Some((self.backend.time(), track));
+        self.request_redraw();
+    }
+    pub fn stop_playback(&mut self) {
+        self.playback = None;
+    }
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+    fn view_sample(&self, time: Duration) -> ViewSample {
+        ViewSample { time, scale: self.scale, view_center: self.view_center }
+    }
+    // record and play back, once per pass of the event loop.
+    // returns true while a track is playing.
+    pub (crate) fn update_track(&mut self) -> bool {
+        let now = self.backend.time();
+        if let Some((start, _)) = self.playback {
+            let t = now.saturating_sub(start);
+            let samples = &self.playback.as_ref().unwrap().1.samples;
+            let next = samples.iter().position(|s| s.time > t);
+            let (sample, done) = match next {
+                Some(0) => (samples[0], false),
+                Some(i) => {
+                    let (a, b) = (samples[i - 1], samples[i]);
+                    let f = (t - a.time).as_secs_f32() / (b.time - a.time).as_secs_f32();
+                    let sample = ViewSample {
+                        time: t,
+                        // zoom is multiplicative, interpolate it that way
+                        scale: a.scale * (b.scale / a.scale).powf(f),
+                        view_center: a.view_center.lerp(b.view_center, f),
+                    };
+                    (sample, false)
+                }
+                None => (*samples.last().unwrap(), true),
+            };
+            self.scale = sample.scale;
+            self.view_center = sample.view_center;
+            self.check_bounds();
+            self.request_redraw();
+            if done {
+                self.playback = None;
+            }
+        }
+        let current = self.view_sample(Duration::default());
+        if let Some((start, ref mut track)) = self.recording {
+            let last = track.samples.last().map(|s| (s.scale, s.view_center));
+            if last != Some((current.scale, current.view_center)) {
+                track.samples.push(ViewSample { time: now.saturating_sub(start), .. current });
+            }
+        }
+        self.playback.is_some()
+    }
+
     pub fn close(&mut self) {
         self.close = true;
     }
@@ -154,67 +1113,325 @@ impl Context {
         self.move_to(self.view_center + delta);
     }
 
-    fn check_bounds(&mut self) {
-        if let Some(bounds) = self.bounds {
+    // returns the edges the view is at, after clamping the view center
+    pub (crate) fn check_bounds(&mut self) -> Vec<Edge> {
+        let mut edges = vec![];
+        if let Some(bounds) = self.visible_bounds() {
             let mut point = self.view_center;
             // scale window size
-            let ws = self.window_size * (1.0 / self.scale);
+            let ws = self.viewport().size() * (1.0 / self.scale);
 
             if ws.x() >= bounds.width() {
                 // center horizontally
                 point.set_x(bounds.origin_x() + bounds.width() * 0.5);
             } else {
+                let min = bounds.origin_x() + ws.x() * 0.5;
+                let max = bounds.origin_x() + bounds.width() - ws.x() * 0.5;
                 let x = point.x();
-                let x = x.max(bounds.origin_x() + ws.x() * 0.5);
-                let x = x.min(bounds.origin_x() + bounds.width() - ws.x() * 0.5);
-                point.set_x(x);
+                if x <= min {
+                    edges.push(Edge::Left);
+                }
+                if x >= max {
+                    edges.push(Edge::Right);
+                }
+                point.set_x(x.max(min).min(max));
             }
             if ws.y() >= bounds.height() {
                 // center vertically
                 point.set_y(bounds.origin_y() + bounds.height() * 0.5);
             } else {
+                let min = bounds.origin_y() + ws.y() * 0.5;
+                let max = bounds.origin_y() + bounds.height() - ws.y() * 0.5;
                 let y = point.y();
-                let y = y.max(bounds.origin_y() + ws.y() * 0.5);
-                let y = y.min(bounds.origin_y() + bounds.height() - ws.y() * 0.5);
-                point.set_y(y);
+                if y <= min {
+                    edges.push(Edge::Top);
+                }
+                if y >= max {
+                    edges.push(Edge::Bottom);
+                }
+                point.set_y(y.max(min).min(max));
             }
             self.view_center = point;
         }
+        self.at_edges = edges.clone();
+        edges
+    }
+
+    // pan so `point` (in scene units) is in the middle of the view, keeping the zoom.
+    // the bounds still apply, a point near their edge ends up off center.
+    pub fn center_on(&mut self, point: Vector2F) {
+        self.move_to(self.page_transform() * point);
+    }
+    // i.e. for a search result, without zooming to it
+    pub fn center_on_rect(&mut self, rect: RectF) {
+        self.center_on(rect.center());
     }
 
     pub fn move_to(&mut self, point: Vector2F) {
-        self.view_center = point;
-        self.check_bounds();
+        let (pan_x, pan_y) = self.config.pan_axes;
+        let x = if pan_x { point.x() } else { self.view_center.x() };
+        let y = if pan_y { point.y() } else { self.view_center.y() };
+        self.view_center = Vector2F::new(x, y);
+        // only report edges the view was not at before, not each move while it is pinned there
+        let before = self.at_edges.clone();
+        let edges = self.check_bounds();
+        for edge in edges {
+            if !before.contains(&edge) && !self.reached_edges.contains(&edge) {
+                self.reached_edges.push(edge);
+            }
+        }
         self.request_redraw();
     }
 
     pub fn set_bounds(&mut self, bounds: RectF) {
         self.bounds = Some(bounds);
-        self.check_bounds();
+        self.apply_fit_mode();
+    }
+    // in scene coordinates, before the rotation of the page
+    pub fn bounds(&self) -> Option<RectF> {
+        self.bounds
+    }
+    // where the view center is within the bounds, 0..1 on each axis. None without bounds.
+    pub fn scroll_fraction(&self) -> Option<Vector2F> {
+        let bounds = self.visible_bounds().filter(|b| b.width() > 0.0 && b.height() > 0.0)?;
+        Some((self.view_center - bounds.origin()) * bounds.size().recip())
+    }
+    // move the view center to `frac` of the bounds, i.e. for a scrollbar drag.
+    // the view is clamped to the bounds as usual, so 0 and 1 show the edges.
+    pub fn set_scroll_fraction(&mut self, frac: Vector2F) {
+        if let Some(bounds) = self.visible_bounds() {
+            self.move_to(bounds.origin() + bounds.size() * frac);
+        }
     }
 
     pub (crate) fn set_scale_factor(&mut self, factor: f32) {
         self.scale_factor = factor;
-        self.check_bounds();
+        self.apply_fit_mode();
         self.request_redraw();
     }
 
+    // how many scene units make an inch. the default of 25.4 means the scene is in mm.
+    pub fn set_document_dpi(&mut self, dpi: f32) {
+        self.document_dpi = dpi;
+        self.apply_fit_mode();
+        self.request_redraw();
+    }
+    pub fn scene_dpi(&self) -> f32 {
+        self.document_dpi
+    }
+    // the scale at which the scene has its physical size, assuming the usual 96 logical pixels per inch.
+    // with scene units in mm and a scale factor of 1, this is `DEFAULT_SCALE`.
+    pub fn actual_size_scale(&self) -> f32 {
+        96.0 * self.scale_factor / self.document_dpi
+    }
+
     pub fn window_size(&self) -> Vector2F {
         self.window_size
     }
     pub fn set_window_size(&mut self, size: Vector2F) {
-        self.window_size = size;
         self.backend.resize(size);
+        self.window_resized(size);
+    }
+    // keeps the scene point at `Config::resize_anchor` in place
+    pub (crate) fn window_resized(&mut self, size: Vector2F) {
+        // i.e. minimized on Windows. keep the last size until the window comes back.
+        self.minimized = size.x() < 1.0 || size.y() < 1.0;
+        if self.minimized {
+            return;
+        }
+        let anchor = self.config.resize_anchor.fraction();
+        let anchor_point = |ctx: &Context| {
+            let viewport = ctx.viewport();
+            viewport.origin() + viewport.size() * anchor
+        };
+        // `view_center` is in unrotated coordinates, the page rotation must not be part of this
+        let scene_point = self.unrotated_view_transform().inverse() * anchor_point(self);
 
+        self.window_size = size;
+        self.apply_fit_mode();
+        let offset = anchor_point(self) - self.viewport().center();
+        self.view_center = scene_point - offset * (1.0 / self.scale);
         self.check_bounds();
         self.request_redraw();
     }
 
+    // the window size `Config::lock_aspect` wants after the user resized to `new_size`,
+    // None if it can stay as it is
+    pub (crate) fn locked_aspect_size(&self, new_size: Vector2F) -> Option<Vector2F> {
+        if !self.config.lock_aspect || new_size.x() < 1.0 || new_size.y() < 1.0 {
+            return None;
+        }
+        let ratio = self.config.aspect_ratio
+            .or_else(|| self.visible_bounds().filter(|b| b.height() > 0.0).map(|b| b.width() / b.height()))
+            .filter(|&r| r > 0.0)?;
+        let change = new_size - self.window_size;
+        // keep the dimension the user dragged
+        let size = if change.x().abs() >= change.y().abs() {
+            Vector2F::new(new_size.x(), (new_size.x() / ratio).round())
+        } else {
+            Vector2F::new((new_size.y() * ratio).round(), new_size.y())
+        };
+        if (size - new_size).length() >= 1.0 {
+            Some(size)
+        } else {
+            None
+        }
+    }
+
+    // limits for resizing by the user, in pixels
+    pub fn set_min_window_size(&mut self, size: Option<Vector2F>) {
+        self.config.min_window_size = size;
+        self.backend.set_min_window_size(size);
+    }
+    pub fn set_max_window_size(&mut self, size: Option<Vector2F>) {
+        self.config.max_window_size_limit = size;
+        self.backend.set_max_window_size(size);
+    }
+
+    pub fn set_maximized(&mut self, maximized: bool) {
+        self.backend.set_maximized(maximized);
+    }
+    pub fn set_minimized(&mut self, minimized: bool) {
+        self.backend.set_minimized(minimized);
+    }
+    pub fn is_maximized(&self) -> bool {
+        self.backend.is_maximized()
+    }
+
+    // the part of the window the scene is shown in.
+    // all of it, unless `Config::aspect_ratio` asks for letterboxing.
+    pub fn viewport(&self) -> RectF {
+        let region = self.viewport_region(self.active_viewport);
+        let size = region.size();
+        match self.config.aspect_ratio {
+            Some(ratio) if ratio > 0.0 && size.y() > 0.0 => {
+                let inner = if size.x() / size.y() > ratio {
+                    Vector2F::new(size.y() * ratio, size.y())
+                } else {
+                    Vector2F::new(size.x(), size.x() / ratio)
+                };
+                RectF::new(region.origin() + (size - inner) * 0.5, inner)
+            }
+            _ => region
+        }
+    }
+    // the window area of viewport `idx` in pixels, all of the window without a split
+    pub (crate) fn viewport_region(&self, idx: usize) -> RectF {
+        match self.viewports.get(idx) {
+            Some(vp) => RectF::new(vp.rect.origin() * self.window_size, vp.rect.size() * self.window_size),
+            None => RectF::new(Vector2F::default(), self.window_size)
+        }
+    }
+
+    // split the window into regions, each with its own view.
+    // pan, zoom and page changes apply to the active viewport, which follows the cursor.
+    // an empty list shows a single view again. on the web only the active viewport is drawn.
+    pub fn set_viewports(&mut self, viewports: Vec<ViewPort>) {
+        self.viewports = viewports;
+        self.active_viewport = 0;
+        if let Some(&vp) = self.viewports.first() {
+            self.load_viewport(vp);
+        }
+        self.request_redraw();
+    }
+    // the viewports, including the current state of the active one
+    pub fn viewports(&self) -> Vec<ViewPort> {
+        let mut viewports = self.viewports.clone();
+        if let Some(vp) = viewports.get_mut(self.active_viewport) {
+            vp.view_center = self.view_center;
+            vp.scale = self.scale;
+            vp.page = self.page_nr;
+        }
+        viewports
+    }
+    // show the current view twice, side by side
+    pub fn duplicate_view(&mut self) {
+        let view = |x| ViewPort {
+            rect: RectF::new(Vector2F::new(x, 0.0), Vector2F::new(0.5, 1.0)),
+            view_center: self.view_center,
+            scale: self.scale,
+            page: self.page_nr,
+        };
+        let viewports = vec![view(0.0), view(0.5)];
+        self.set_viewports(viewports);
+    }
+    // linked viewports share pan and zoom, pages stay separate
+    pub fn set_viewports_linked(&mut self, linked: bool) {
+        self.linked_viewports = linked;
+    }
+    pub fn active_viewport(&self) -> usize {
+        self.active_viewport
+    }
+    pub fn activate_viewport(&mut self, idx: usize) {
+        if idx == self.active_viewport || idx >= self.viewports.len() {
+            return;
+        }
+        self.store_viewport();
+        self.active_viewport = idx;
+        let vp = self.viewports[idx];
+        self.load_viewport(vp);
+    }
+    // the viewport containing `window_point`
+    pub fn viewport_at(&self, window_point: Vector2F) -> Option<usize> {
+        (0 .. self.viewports.len()).find(|&idx| self.viewport_region(idx).contains_point(window_point))
+    }
+    pub (crate) fn store_viewport(&mut self) {
+        let (view_center, scale, page) = (self.view_center, self.scale, self.page_nr);
+        if let Some(vp) = self.viewports.get_mut(self.active_viewport) {
+            vp.view_center = view_center;
+            vp.scale = scale;
+            vp.page = page;
+        }
+        if self.linked_viewports {
+            for vp in self.viewports.iter_mut() {
+                vp.view_center = view_center;
+                vp.scale = scale;
+            }
+        }
+    }
+    pub (crate) fn load_viewport(&mut self, vp: ViewPort) {
+        self.view_center = vp.view_center;
+        self.scale = vp.scale;
+        self.page_nr = vp.page.min(self.num_pages.saturating_sub(1));
+    }
+
+    // run `f` with the view set to `tr` (scene units to window pixels, including the page rotation),
+    // then restore the current view. `view_transform` returns `tr` as it is,
+    // `scale` and the view center follow its translation and uniform scale.
+    pub fn with_temporary_transform<R>(&mut self, tr: Transform2F, f: impl FnOnce(&mut Context) -> R) -> R {
+        let (scale, view_center, transform_override) = (self.scale, self.view_center, self.transform_override);
+        self.scale = tr.matrix.det().abs().sqrt();
+        self.view_center = self.page_transform() * (tr.inverse() * self.viewport().center());
+        self.transform_override = Some(tr);
+        let result = f(self);
+        self.scale = scale;
+        self.view_center = view_center;
+        self.transform_override = transform_override;
+        result
+    }
+
     pub fn view_transform(&self) -> Transform2F {
-        Transform2F::from_translation(self.window_size * 0.5) *
+        if let Some(tr) = self.transform_override {
+            return tr;
+        }
+        let mut tr = self.unrotated_view_transform() * self.page_transform();
+        if self.config.pixel_snap && self.is_integer_zoom() {
+            tr.vector = tr.vector.round();
+        }
+        tr
+    }
+    // the view without the rotation of the current page, `view_center` is in these coordinates
+    fn unrotated_view_transform(&self) -> Transform2F {
+        Transform2F::from_translation(self.viewport().center()) *
             Transform2F::from_scale(self.scale) *
             Transform2F::from_translation(-self.view_center)
     }
+    // 1:2, 1:1, 2:1 and so on, relative to DEFAULT_SCALE
+    fn is_integer_zoom(&self) -> bool {
+        let ratio = self.scale / DEFAULT_SCALE;
+        let near_integer = |r: f32| r >= 1.0 && (r - r.round()).abs() < 0.01;
+        near_integer(ratio) || near_integer(ratio.recip())
+    }
     pub fn set_view_box(&mut self, view_box: RectF) {
         self.window_size = view_box.size();
         self.check_bounds();
@@ -238,6 +1455,211 @@ impl Context {
     pub fn set_icon(&mut self, icon: Icon) {
         self.backend.set_icon(icon);
     }
+
+    // open `url` in the browser. only http, https and mailto links are opened,
+    // links from documents can not be trusted to be anything else.
+    pub fn open_url(&self, url: &str) {
+        let scheme = url.split_once(':').map_or("", |(scheme, _)| scheme);
+        if !["http", "https", "mailto"].iter().any(|s| s.eq_ignore_ascii_case(scheme)) {
+            warn!("not opening {:?}, only http, https and mailto links are allowed", url);
+            return;
+        }
+        self.backend.open_url(url);
+    }
+
+    pub fn set_grid(&mut self, grid: Option<GridSpec>) {
+        self.config.grid = grid;
+        self.invalidate_render_cache();
+        self.request_redraw();
+    }
+
+    pub fn set_background(&mut self, color: ColorF) {
+        self.config.background = color;
+        self.invalidate_render_cache();
+        self.request_redraw();
+    }
+    pub fn background(&self) -> ColorF {
+        self.config.background
+    }
+
+    pub fn set_post_process(&mut self, effect: Option<PostProcess>) {
+        self.config.post_process = effect;
+        self.backend.set_post_process(effect);
+        self.request_redraw();
+    }
+
+    // drop all frames kept for `Config::render_cache_budget`
+    pub fn invalidate_render_cache(&mut self) {
+        self.backend.invalidate_render_cache();
+    }
+    pub fn render_cache_stats(&self) -> RenderCacheStats {
+        self.backend.render_cache_stats()
+    }
+
+    // pathfinder compiles its shaders when they are first used. call this in `Interactive::init`
+    // to do that with a tiny offscreen frame, so the first real frame is not slower than the rest.
+    pub fn prewarm(&mut self) {
+        self.backend.prewarm();
+    }
+
+    pub fn set_debug_ui(&mut self, show: bool) {
+        self.config.debug_ui = show;
+        self.backend.set_debug_ui(show);
+        self.invalidate_render_cache();
+        self.request_redraw();
+    }
+    pub fn toggle_debug_ui(&mut self) {
+        self.set_debug_ui(!self.config.debug_ui);
+    }
+
+    // the system theme, if the platform reports it
+    pub fn theme(&self) -> Option<Theme> {
+        self.theme
+    }
+
+    // opacity of the whole window, independent of the scene.
+    // winit has no window opacity, so native windows scale the alpha of every pixel instead.
+    // that only shows with `Config::transparent` and a compositing window manager.
+    // on the web, it is the CSS opacity of the canvas.
+    pub fn set_window_opacity(&mut self, alpha: f32) {
+        let alpha = alpha.max(0.0).min(1.0);
+        if alpha != self.window_opacity {
+            if alpha < 1.0 && !self.config.transparent && cfg!(not(target_arch="wasm32")) {
+                warn!("window opacity needs Config::transparent");
+            }
+            self.window_opacity = alpha;
+            self.invalidate_render_cache();
+            self.request_redraw();
+        }
+    }
+    pub fn window_opacity(&self) -> f32 {
+        self.window_opacity
+    }
+
+    pub fn set_invert(&mut self, invert: bool) {
+        if invert != self.config.invert {
+            self.config.invert = invert;
+            self.invalidate_render_cache();
+            self.request_redraw();
+        }
+    }
+    pub fn invert(&self) -> bool {
+        self.config.invert
+    }
+
+    // overlays the viewer draws on top of the scene of the item.
+    // `area` is the region to cover, in scene coordinates. `background` is the one the frame is cleared to.
+    // `to_scene` maps window pixels to the coordinates of `scene`, the identity unless the
+    // renderer transforms the scene itself (as on the web).
+    pub (crate) fn decorate_scene(&self, scene: &mut Scene, area: RectF, background: ColorF, to_scene: Transform2F) {
+        if self.config.transparency_checkerboard {
+            let mut background = Scene::new();
+            background.set_view_box(scene.view_box());
+            util::checkerboard(&mut background, area);
+            background.append_scene(std::mem::replace(scene, Scene::new()));
+            *scene = background;
+        }
+        if !self.text_selection.is_empty() {
+            util::highlight(scene, &self.text_selection, to_scene * self.view_transform());
+        }
+        if self.config.aspect_ratio.is_some() {
+            util::letterbox(scene, to_scene * self.viewport(), area, background);
+        }
+        if let Some(ref grid) = self.config.grid {
+            util::grid(scene, grid, to_scene * self.view_transform(), area);
+        }
+        if self.config.invert {
+            util::invert(scene, area);
+        }
+        // the web backend sets the opacity of the canvas instead
+        if cfg!(not(target_arch="wasm32")) && self.window_opacity < 1.0 {
+            util::fade(scene, area, self.window_opacity);
+        }
+    }
+
+    // one page per scene, sized to its view box. scene units are taken as mm.
+    #[cfg(feature="pdf")]
+    pub fn export_pdf(&self, scenes: &[Scene]) -> Vec<u8> {
+        pdf::export(scenes)
+    }
+
+    // thumbnail of `page` at `Config::thumbnail_size`.
+    // returns None until it has been rendered. one thumbnail per pass of the event loop: its scene
+    // is asked from the item, built on pathfinder's thread pool and drawn on the next pass.
+    // a redraw is requested once all asked for are ready.
+    // native windows only, on the web this always returns None.
+    pub fn thumbnail(&mut self, page: usize) -> Option<RawImage> {
+        let size = self.config.thumbnail_size;
+        match self.thumbnails.get(&(page, (size.x(), size.y()))) {
+            Some(image) => Some(image.clone()),
+            None => {
+                if cfg!(not(target_arch="wasm32")) && !self.pending_thumbnails.contains(&page) {
+                    self.pending_thumbnails.push(page);
+                }
+                None
+            }
+        }
+    }
+    // a finished scene for `page`, shown instead of calling `Interactive::scene` for it.
+    // pages are usually built on another thread: send the scene with the `Emitter` and pass it here
+    // in `Interactive::event`. until then `scene` is asked as usual and can draw a placeholder.
+    // like a `Scene` item, it is shown as it is, so it has to be built for the current view.
+    // it is only used while the view stays the same: after a pan, zoom or rotation
+    // `scene` is asked again and can start a build for the new view.
+    pub fn set_page_scene(&mut self, page: usize, scene: Scene) {
+        let tr = self.view_transform();
+        self.page_scenes.insert(page, (scene, tr));
+        self.invalidate_render_cache();
+        self.thumbnails.retain(|&(p, _), _| p != page);
+        if page == self.page_nr {
+            self.request_redraw();
+        }
+    }
+    // the scene for `page` if it still matches the current view
+    pub fn page_scene(&self, page: usize) -> Option<&Scene> {
+        let view = self.view_transform();
+        self.page_scenes.get(&page).filter(|&&(_, tr)| tr == view).map(|(scene, _)| scene)
+    }
+    pub fn clear_page_scenes(&mut self) {
+        self.page_scenes.clear();
+        self.invalidate_render_cache();
+        self.request_redraw();
+    }
+    // the scene given to `set_page_scene` for `page` if it was built for this view, or the one of `item`
+    pub (crate) fn page_or_item_scene<T: Interactive + ?Sized>(&mut self, item: &mut T, page: usize) -> Scene {
+        match self.page_scene(page) {
+            Some(scene) => scene.clone(),
+            None if page == self.page_nr => item.scene(self),
+            None => item.scene_for_page(self, page),
+        }
+    }
+
+    // drop all thumbnails, i.e. when the document changed
+    pub fn clear_thumbnails(&mut self) {
+        self.thumbnails.clear();
+        self.pending_thumbnails.clear();
+    }
+
+    #[cfg(unix)]
+    pub fn gl_config_info(&self) -> &gl::GlConfigInfo {
+        self.backend.gl_config_info()
+    }
+
+    #[cfg(unix)]
+    pub fn render_to_buffer(&mut self, mut scene: Scene) -> RawImage {
+        let area = RectF::new(Vector2F::default(), self.backend.framebuffer_size());
+        self.decorate_scene(&mut scene, area, self.config.background, Transform2F::default());
+        self.backend.render_to_buffer(scene)
+    }
+
+    // render into a GL texture of (at least) `size` pixels, returns the texture name and its actual size.
+    // the texture is reused by the next call.
+    #[cfg(unix)]
+    pub fn render_to_texture(&mut self, mut scene: Scene, size: Vector2I) -> (u32, Vector2I) {
+        let area = RectF::new(Vector2F::default(), size.to_f32());
+        self.decorate_scene(&mut scene, area, self.config.background, Transform2F::default());
+        self.backend.render_to_texture(scene, size)
+    }
 }
 
 fn view_box(scene: &Scene) -> RectF {
@@ -248,3 +1670,68 @@ fn view_box(scene: &Scene) -> RectF {
         view_box
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{RawImage, PixelFormat};
+
+    // rows padded to `stride` with 0xff, so reading the padding shows up in the results
+    fn image(width: u32, height: u32, stride: u32, format: PixelFormat, pixel: impl Fn(u32, u32) -> [u8; 4]) -> RawImage {
+        let mut data = vec![0xff; (stride * height) as usize];
+        for y in 0 .. height {
+            for x in 0 .. width {
+                let start = (y * stride + x * 4) as usize;
+                data[start .. start + 4].copy_from_slice(&pixel(x, y));
+            }
+        }
+        RawImage { data, width, height, stride, format }
+    }
+
+    #[test]
+    fn row_skips_padding() {
+        let img = image(3, 2, 16, PixelFormat::Rgba8, |x, y| [x as u8, y as u8, 0, 255]);
+        assert_eq!(img.row(0), &[0, 0, 0, 255, 1, 0, 0, 255, 2, 0, 0, 255]);
+        assert_eq!(img.row(1), &[0, 1, 0, 255, 1, 1, 0, 255, 2, 1, 0, 255]);
+    }
+
+    #[test]
+    fn unmultiply_alpha() {
+        let mut img = image(3, 1, 12, PixelFormat::Rgba8Premultiplied, |x, _| match x {
+            0 => [0, 0, 0, 0],
+            1 => [10, 20, 30, 255],
+            _ => [64, 32, 0, 128],
+        });
+        img.unmultiply();
+        assert_eq!(img.format, PixelFormat::Rgba8);
+        assert_eq!(img.row(0), &[0, 0, 0, 0, 10, 20, 30, 255, 128, 64, 0, 128]);
+
+        // already straight alpha, nothing to do
+        let mut img = image(1, 1, 4, PixelFormat::Rgba8, |_, _| [64, 32, 0, 128]);
+        img.unmultiply();
+        assert_eq!(img.row(0), &[64, 32, 0, 128]);
+    }
+
+    #[test]
+    fn downsample_odd_width() {
+        let img = image(5, 3, 24, PixelFormat::Rgba8, |x, y| [(x * 10) as u8, (y * 10) as u8, 0, 255]);
+        let small = img.downsample(2);
+        assert_eq!((small.width, small.height, small.stride), (2, 1, 8));
+        assert_eq!(small.data, vec![5, 5, 0, 255, 25, 5, 0, 255]);
+    }
+
+    #[test]
+    fn downsample_factor_larger_than_image() {
+        let img = image(3, 3, 16, PixelFormat::Rgba8, |_, _| [1, 2, 3, 4]);
+        let small = img.downsample(4);
+        assert_eq!((small.width, small.height), (0, 0));
+        assert!(small.data.is_empty());
+    }
+
+    #[test]
+    fn downsample_by_one_keeps_the_image() {
+        let img = image(3, 2, 16, PixelFormat::Rgba8Premultiplied, |x, y| [x as u8, y as u8, 0, 255]);
+        let same = img.downsample(1);
+        assert_eq!((same.width, same.height, same.stride, same.format), (3, 2, 16, PixelFormat::Rgba8Premultiplied));
+        assert_eq!(same.data, img.data);
+    }
+}
@@ -0,0 +1,69 @@
+use printpdf::{PdfDocument, PdfLayerReference, Mm, Pt, Color, Rgb};
+use printpdf::lopdf::{content::Operation, Object};
+use pathfinder_content::{
+    outline::{Outline, ContourIterFlags},
+    segment::SegmentKind,
+};
+use pathfinder_geometry::{
+    vector::Vector2F,
+    rect::RectF,
+};
+use pathfinder_renderer::scene::Scene;
+use crate::view_box;
+
+pub fn export(scenes: &[Scene]) -> Vec<u8> {
+    let doc = PdfDocument::empty("pathfinder_view");
+    for scene in scenes {
+        let view_box = view_box(scene);
+        let (page, layer) = doc.add_page(Mm(view_box.width() as f64), Mm(view_box.height() as f64), "scene");
+        let layer = doc.get_page(page).get_layer(layer);
+
+        for (paint, outline, _) in scene.paths() {
+            let color = paint.base_color().to_f32();
+            layer.set_fill_color(Color::Rgb(Rgb::new(color.r() as f64, color.g() as f64, color.b() as f64, None)));
+            add_outline(&layer, outline, view_box);
+        }
+    }
+    doc.save_to_bytes().expect("failed to write PDF")
+}
+
+// all contours go into one path, so holes stay open.
+// `Scene::paths` does not report the fill rule, this uses pathfinder's default (nonzero).
+fn add_outline(layer: &PdfLayerReference, outline: &Outline, view_box: RectF) {
+    // pdf has the origin in the bottom left corner and counts in points
+    let point = |p: Vector2F| vec![
+        Object::Real(Pt::from(Mm((p.x() - view_box.origin_x()) as f64)).0 as _),
+        Object::Real(Pt::from(Mm((view_box.max_y() - p.y()) as f64)).0 as _),
+    ];
+
+    let mut empty = true;
+    for contour in outline.contours() {
+        let mut started = false;
+        for segment in contour.iter(ContourIterFlags::empty()) {
+            if !started {
+                layer.add_operation(Operation::new("m", point(segment.baseline.from())));
+                started = true;
+            }
+            match segment.kind {
+                SegmentKind::None => continue,
+                SegmentKind::Line => {
+                    layer.add_operation(Operation::new("l", point(segment.baseline.to())));
+                }
+                SegmentKind::Quadratic | SegmentKind::Cubic => {
+                    let cubic = segment.to_cubic();
+                    let mut operands = point(cubic.ctrl.from());
+                    operands.extend(point(cubic.ctrl.to()));
+                    operands.extend(point(cubic.baseline.to()));
+                    layer.add_operation(Operation::new("c", operands));
+                }
+            }
+        }
+        if started && contour.is_closed() {
+            layer.add_operation(Operation::new("h", vec![]));
+        }
+        empty &= !started;
+    }
+    if !empty {
+        layer.add_operation(Operation::new("f", vec![]));
+    }
+}
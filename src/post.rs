@@ -0,0 +1,149 @@
+use gl::types::{GLuint, GLint, GLenum};
+use pathfinder_geometry::vector::Vector2I;
+use pathfinder_renderer::gpu::options::RendererLevel;
+use crate::PostProcess;
+
+const VERTEX_SHADER: &str = "
+out vec2 v_uv;
+void main() {
+    // one triangle covering the viewport
+    vec2 p = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+    v_uv = p;
+    gl_Position = vec4(p * 2.0 - 1.0, 0.0, 1.0);
+}
+";
+
+const FRAGMENT_HEADER: &str = "
+precision highp float;
+uniform sampler2D u_texture;
+uniform vec2 u_texel;
+in vec2 v_uv;
+out vec4 o_color;
+";
+
+fn fragment_main(effect: PostProcess) -> &'static str {
+    match effect {
+        PostProcess::Grayscale => "
+void main() {
+    vec4 c = texture(u_texture, v_uv);
+    float l = dot(c.rgb, vec3(0.2126, 0.7152, 0.0722));
+    o_color = vec4(vec3(l), c.a);
+}
+",
+        PostProcess::Sepia => "
+void main() {
+    vec4 c = texture(u_texture, v_uv);
+    mat3 sepia = mat3(
+        0.393, 0.349, 0.272,
+        0.769, 0.686, 0.534,
+        0.189, 0.168, 0.131
+    );
+    o_color = vec4(min(sepia * c.rgb, vec3(1.0)), c.a);
+}
+",
+        PostProcess::EdgeDetect => "
+float l(vec2 d) {
+    return dot(texture(u_texture, v_uv + d * u_texel).rgb, vec3(0.2126, 0.7152, 0.0722));
+}
+void main() {
+    float gx = l(vec2(1, -1)) + 2.0 * l(vec2(1, 0)) + l(vec2(1, 1))
+             - l(vec2(-1, -1)) - 2.0 * l(vec2(-1, 0)) - l(vec2(-1, 1));
+    float gy = l(vec2(-1, 1)) + 2.0 * l(vec2(0, 1)) + l(vec2(1, 1))
+             - l(vec2(-1, -1)) - 2.0 * l(vec2(0, -1)) - l(vec2(1, -1));
+    o_color = vec4(vec3(1.0 - min(length(vec2(gx, gy)), 1.0)), 1.0);
+}
+",
+    }
+}
+
+// a full screen pass that reads a texture and writes the effect to the bound framebuffer
+pub struct PostProcessor {
+    effect: PostProcess,
+    program: GLuint,
+    vertex_array: GLuint,
+    texture_location: GLint,
+    texel_location: GLint,
+}
+impl PostProcessor {
+    pub fn new(effect: PostProcess, level: RendererLevel) -> Self {
+        let version = match level {
+            RendererLevel::D3D9 => "#version 300 es\n",
+            RendererLevel::D3D11 => "#version 330\n",
+        };
+        unsafe {
+            let vertex = compile(gl::VERTEX_SHADER, &[version, VERTEX_SHADER]);
+            let fragment = compile(gl::FRAGMENT_SHADER, &[version, FRAGMENT_HEADER, fragment_main(effect)]);
+
+            let program = gl::CreateProgram();
+            gl::AttachShader(program, vertex);
+            gl::AttachShader(program, fragment);
+            gl::LinkProgram(program);
+            gl::DeleteShader(vertex);
+            gl::DeleteShader(fragment);
+
+            let mut status = 0;
+            gl::GetProgramiv(program, gl::LINK_STATUS, &mut status);
+            if status == 0 {
+                error!("failed to link post process shader");
+            }
+
+            let mut vertex_array = 0;
+            gl::GenVertexArrays(1, &mut vertex_array);
+
+            PostProcessor {
+                effect,
+                program,
+                vertex_array,
+                texture_location: gl::GetUniformLocation(program, b"u_texture\0".as_ptr().cast()),
+                texel_location: gl::GetUniformLocation(program, b"u_texel\0".as_ptr().cast()),
+            }
+        }
+    }
+    pub fn effect(&self) -> PostProcess {
+        self.effect
+    }
+    // draw `texture` of `size` into the default framebuffer
+    pub fn apply(&self, texture: GLuint, size: Vector2I) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Viewport(0, 0, size.x(), size.y());
+            gl::Disable(gl::BLEND);
+            gl::UseProgram(self.program);
+            gl::BindVertexArray(self.vertex_array);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::Uniform1i(self.texture_location, 0);
+            gl::Uniform2f(self.texel_location, 1.0 / size.x() as f32, 1.0 / size.y() as f32);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+            gl::UseProgram(0);
+        }
+    }
+}
+impl Drop for PostProcessor {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.program);
+            gl::DeleteVertexArrays(1, &self.vertex_array);
+        }
+    }
+}
+
+unsafe fn compile(kind: GLenum, sources: &[&str]) -> GLuint {
+    let shader = gl::CreateShader(kind);
+    let ptrs: Vec<_> = sources.iter().map(|s| s.as_ptr().cast()).collect();
+    let lengths: Vec<GLint> = sources.iter().map(|s| s.len() as GLint).collect();
+    gl::ShaderSource(shader, sources.len() as _, ptrs.as_ptr(), lengths.as_ptr());
+    gl::CompileShader(shader);
+
+    let mut status = 0;
+    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
+    if status == 0 {
+        let mut len = 0;
+        gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
+        let mut log = vec![0u8; len.max(1) as usize];
+        gl::GetShaderInfoLog(shader, len, std::ptr::null_mut(), log.as_mut_ptr().cast());
+        error!("failed to compile post process shader: {}", String::from_utf8_lossy(&log));
+    }
+    shader
+}
@@ -1,15 +1,22 @@
 
-use winit::event::{Event, ElementState as WinitElementState, WindowEvent, MouseButton, MouseScrollDelta, StartCause};
+use winit::event::{Event, DeviceEvent, ElementState as WinitElementState, WindowEvent, MouseButton, MouseScrollDelta, StartCause, Touch, TouchPhase};
 use winit::event_loop::{ControlFlow, EventLoopProxy};
-use winit::keyboard::{ModifiersState};
+use winit::keyboard::{ModifiersState, KeyCode};
+use winit::window::CursorGrabMode;
 use winit::platform::{run_return::EventLoopExtRunReturn};
 use winit::dpi::{PhysicalSize, PhysicalPosition};
-use crate::view::{Interactive};
-use crate::{Config, Context};
-use crate::{Icon};
-use pathfinder_geometry::vector::{Vector2F, vec2f};
+use crate::view::{Interactive, RawKey};
+use winit::platform::scancode::KeyCodeExtScancode;
+use crate::{Config, Context, LoopMode, IdlePolicy};
+use crate::{Icon, RawImage, PixelFormat, PostProcess, RenderCacheStats};
+use crate::cache::CacheKey;
+use pathfinder_geometry::vector::{Vector2F, Vector2I, vec2f};
+use pathfinder_geometry::rect::{RectF, RectI};
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_color::ColorF;
 use pathfinder_renderer::{
-    options::{BuildOptions, RenderTransform},
+    scene::Scene,
+    gpu::options::RendererLevel,
 };
 use std::time::{Instant, Duration};
 
@@ -26,13 +33,18 @@ impl<E: 'static> Clone for Emitter<E> {
 }
 pub struct Backend {
     window: crate::gl::GlWindow,
+    start: Instant,
 }
 impl Backend {
     pub fn new(window: crate::gl::GlWindow) -> Backend {
         Backend {
             window,
+            start: Instant::now(),
         }
     }
+    pub fn time(&self) -> Duration {
+        self.start.elapsed()
+    }
     pub fn resize(&mut self, size: Vector2F) {
         self.window.resize(size);
     }
@@ -49,7 +61,334 @@ impl Backend {
             icon.height
         ).unwrap()));
     }
+    pub fn set_post_process(&mut self, effect: Option<PostProcess>) {
+        self.window.set_post_process(effect);
+    }
+    pub fn set_debug_ui(&mut self, show: bool) {
+        self.window.set_debug_ui(show);
+    }
+    pub fn prewarm(&mut self) {
+        self.window.prewarm();
+    }
+    pub fn invalidate_render_cache(&mut self) {
+        self.window.clear_render_cache();
+    }
+    pub fn render_cache_stats(&self) -> RenderCacheStats {
+        self.window.render_cache_stats()
+    }
+    pub fn open_url(&self, url: &str) {
+        let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+        match std::process::Command::new(opener).arg(url).spawn() {
+            // wait on another thread, so the child does not stay around as a zombie
+            Ok(mut child) => {
+                std::thread::spawn(move || child.wait());
+            }
+            Err(e) => warn!("failed to open {}: {}", url, e),
+        }
+    }
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {
+        let mode = match fullscreen {
+            true => Some(winit::window::Fullscreen::Borderless(None)),
+            false => None
+        };
+        self.window.window().set_fullscreen(mode);
+    }
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.window.window().set_cursor_visible(visible);
+    }
+    pub fn set_cursor_grab(&mut self, mode: CursorGrabMode) {
+        let window = self.window.window();
+        if let Err(e) = window.set_cursor_grab(mode) {
+            // macOS can only lock, X11 only confine
+            let fallback = match mode {
+                CursorGrabMode::Confined => CursorGrabMode::Locked,
+                CursorGrabMode::Locked => CursorGrabMode::Confined,
+                CursorGrabMode::None => {
+                    warn!("failed to release the cursor: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = window.set_cursor_grab(fallback) {
+                warn!("failed to grab the cursor: {}", e);
+            }
+        }
+    }
+    pub fn set_maximized(&mut self, maximized: bool) {
+        self.window.window().set_maximized(maximized);
+    }
+    pub fn set_minimized(&mut self, minimized: bool) {
+        self.window.window().set_minimized(minimized);
+    }
+    pub fn is_maximized(&self) -> bool {
+        self.window.window().is_maximized()
+    }
+    pub fn set_min_window_size(&mut self, size: Option<Vector2F>) {
+        self.window.set_min_size(size);
+    }
+    pub fn set_max_window_size(&mut self, size: Option<Vector2F>) {
+        self.window.set_max_size(size);
+    }
+    pub fn gl_config_info(&self) -> &crate::gl::GlConfigInfo {
+        self.window.config_info()
+    }
+    pub fn framebuffer_size(&self) -> Vector2F {
+        self.window.framebuffer_size().to_f32()
+    }
+    pub fn render_to_buffer(&mut self, scene: Scene) -> RawImage {
+        let options = self.window.build_options();
+        self.window.render_to_buffer(scene, options)
+    }
+    pub fn render_to_texture(&mut self, scene: Scene, size: Vector2I) -> (u32, Vector2I) {
+        let options = self.window.build_options();
+        self.window.render_to_texture(scene, options, size)
+    }
+}
+impl Context {
+    // build the scene of `item` and present it right away, for hosts that drive their own frames.
+    // returns after the buffers were swapped.
+    pub fn render_now<T: Interactive + ?Sized>(&mut self, item: &mut T) {
+        if self.minimized {
+            return;
+        }
+        self.backend.window.resized(self.window_size);
+        let scene = self.prepare_scene(item);
+        let options = self.backend.window.build_options();
+        self.backend.window.render(scene, options);
+        self.redraw_requested = false;
+    }
+
+    // apply the cursor move and resize held back by `Config::coalesce_events`
+    pub(crate) fn flush_events<T: Interactive + ?Sized>(&mut self, item: &mut T, cursor_pos: &mut Vector2F) {
+        if let Some(size) = self.pending_resize.take() {
+            window_resized(self, size);
+        }
+        if let Some(pos) = self.pending_cursor.take() {
+            cursor_moved(item, self, cursor_pos, pos);
+        }
+    }
+
+    // render `item` with `tr` (scene units to image pixels) instead of the current view
+    // into an image of `dest` pixels, i.e. to export at a given scale. nothing is drawn on top.
+    // `supersample` renders that many times larger and scales down, like `Config::thumbnail_supersample`.
+    pub fn render_with_transform<T: Interactive + ?Sized>(&mut self, item: &mut T, tr: Transform2F, dest: Vector2I, supersample: u32) -> RawImage {
+        let background = item.background(self).unwrap_or(self.config.background);
+        self.backend.window.set_background(background);
+        let area = RectF::new(Vector2F::default(), dest.to_f32());
+        self.transform_override = Some(tr);
+        let scene = self.item_scene(item, area);
+        self.transform_override = None;
+        self.backend.window.render_to_image(scene, dest, supersample)
+    }
+
+    fn render_cache_key(&self, background: ColorF) -> Option<CacheKey> {
+        if !self.backend.window.render_cache_enabled() || self.viewports.len() > 1 {
+            return None;
+        }
+        Some(CacheKey {
+            page: self.page_nr,
+            scale: CacheKey::quantize_scale(self.scale),
+            view_center: self.view_center,
+            window_size: self.window_size,
+            background: background.to_u8(),
+            theme: self.theme,
+            post_process: self.config.post_process,
+        })
+    }
+
+    // one scene per viewport, each in the coordinates of its window region
+    fn prepare_viewport_scenes<T: Interactive + ?Sized>(&mut self, item: &mut T) -> Vec<(Scene, RectI)> {
+        self.store_viewport();
+        let background = item.background(self).unwrap_or(self.config.background);
+        let active = self.active_viewport;
+        let mut scenes = Vec::with_capacity(self.viewports.len());
+        for idx in 0 .. self.viewports.len() {
+            self.active_viewport = idx;
+            let vp = self.viewports[idx];
+            self.load_viewport(vp);
+
+            let region = self.viewport_region(idx);
+            let rect = RectI::from_points(region.origin().round().to_i32(), region.lower_right().round().to_i32());
+            let mut scene = self.item_scene(item, region);
+            self.decorate_scene(&mut scene, region, background, Transform2F::default());
+            scenes.push((scene, rect));
+        }
+        self.active_viewport = active;
+        let vp = self.viewports[active];
+        self.load_viewport(vp);
+        scenes
+    }
+
+    // the transformed scene of `item`, or an error scene covering `area` if it panicked
+    fn item_scene<T: Interactive + ?Sized>(&mut self, item: &mut T, area: RectF) -> Scene {
+        let catch = self.config.catch_panics;
+        let scene = catch_panic(catch, || {
+            let scene = self.page_or_item_scene(item, self.page_nr);
+            item.transform_scene(self, scene)
+        });
+        scene.unwrap_or_else(|| crate::util::error_scene(area))
+    }
+
+    // the scene of `item` with everything the viewer adds to it
+    fn prepare_scene<T: Interactive + ?Sized>(&mut self, item: &mut T) -> Scene {
+        let background = item.background(self).unwrap_or(self.config.background);
+        self.backend.window.set_background(background);
+        let area = RectF::new(Vector2F::default(), self.backend.framebuffer_size());
+        let mut scene = self.item_scene(item, area);
+        self.decorate_scene(&mut scene, area, background, Transform2F::default());
+        scene
+    }
+}
+
+#[cfg(feature="clipboard")]
+fn copy_view_to_clipboard<T: Interactive>(item: &mut T, ctx: &mut Context) {
+    ctx.backend.window.resized(ctx.window_size);
+    let scene = ctx.prepare_scene(item);
+    let mut image = ctx.backend.render_to_buffer(scene);
+    image.unmultiply();
+    let bytes: Vec<u8> = (0 .. image.height).flat_map(|y| image.row(y).iter().cloned()).collect();
+    let data = arboard::ImageData {
+        width: image.width as usize,
+        height: image.height as usize,
+        bytes: bytes.into(),
+    };
+    if let Err(e) = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_image(data)) {
+        warn!("failed to copy the view to the clipboard: {}", e);
+    }
+    // the back buffer now holds the capture, draw the window again
+    ctx.request_redraw();
+}
+#[cfg(not(feature="clipboard"))]
+fn copy_view_to_clipboard<T: Interactive>(_item: &mut T, _ctx: &mut Context) {
+    warn!("copying the view needs the clipboard feature");
+}
+
+#[cfg(feature="clipboard")]
+fn paste_image_from_clipboard<T: Interactive>(item: &mut T, ctx: &mut Context) {
+    // most of the time there is text or nothing, that is not an error
+    let image = match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_image()) {
+        Ok(image) => image,
+        Err(_) => return,
+    };
+    let format = PixelFormat::Rgba8;
+    let image = RawImage {
+        data: image.bytes.into_owned(),
+        width: image.width as u32,
+        height: image.height as u32,
+        stride: image.width as u32 * format.bytes_per_pixel(),
+        format,
+    };
+    item.image_pasted(ctx, image);
+}
+#[cfg(not(feature="clipboard"))]
+fn paste_image_from_clipboard<T: Interactive>(_item: &mut T, _ctx: &mut Context) {}
+
+fn cursor_moved<T: Interactive + ?Sized>(item: &mut T, ctx: &mut Context, cursor_pos: &mut Vector2F, new_pos: Vector2F) {
+    ctx.set_cursor_hidden(false);
+    let cursor_delta = new_pos - *cursor_pos;
+    *cursor_pos = new_pos;
+    ctx.cursor_pos = Some(new_pos);
+
+    if ctx.dragging {
+        ctx.move_by(cursor_delta * (-1.0 / ctx.scale));
+    } else {
+        if let Some(idx) = ctx.viewport_at(new_pos) {
+            ctx.activate_viewport(idx);
+        }
+        if ctx.selecting {
+            let scene_pos = ctx.view_transform().inverse() * new_pos;
+            ctx.update_selection(scene_pos);
+        }
+        item.cursor_moved(ctx, new_pos);
+        if ctx.pointer_down {
+            let page_nr = ctx.page_nr;
+            item.pointer_input(ctx, page_nr, new_pos, 1.0, WinitElementState::Pressed);
+        }
+    }
+}
+
+fn window_resized(ctx: &mut Context, physical_size: Vector2F) {
+    if let Some(size) = ctx.locked_aspect_size(physical_size) {
+        ctx.set_window_size(size);
+    } else {
+        ctx.window_resized(physical_size);
+    }
+}
+
+// speed of `Config::smooth_key_zoom` in log2 of the zoom factor per second, and how often it is updated
+const KEY_ZOOM_RATE: f32 = 1.5;
+const KEY_ZOOM_INTERVAL: Duration = Duration::from_millis(16);
+// frame interval of a playing track without `Context::set_max_fps`
+const PLAYBACK_INTERVAL: Duration = Duration::from_millis(16);
+
+// the button that pans and selects
+fn primary_button(config: &Config) -> MouseButton {
+    match config.swap_mouse_buttons {
+        false => MouseButton::Left,
+        true => MouseButton::Right,
+    }
+}
+
+// with `catch_panics`, a panic in `f` is logged and gives None instead of taking down the process
+fn catch_panic<R>(catch_panics: bool, f: impl FnOnce() -> R) -> Option<R> {
+    if !catch_panics {
+        return Some(f());
+    }
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(r) => Some(r),
+        Err(payload) => {
+            let msg = payload.downcast_ref::<&str>().map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".into());
+            error!("recovered from a panic: {}", msg);
+            None
+        }
+    }
+}
+
+// make sure the loop runs again at `t` at the latest
+fn wake_up_at(control_flow: &mut ControlFlow, t: Instant) {
+    *control_flow = match *control_flow {
+        ControlFlow::Poll => ControlFlow::Poll,
+        ControlFlow::WaitUntil(t0) => ControlFlow::WaitUntil(t0.min(t)),
+        _ => ControlFlow::WaitUntil(t),
+    };
+}
+
+// returns true if a one shot frame was rendered
+#[cfg(feature="png")]
+fn render_one_shot<T: Interactive>(item: &mut T, ctx: &mut Context) -> bool {
+    let one_shot = match ctx.config.one_shot.take() {
+        Some(one_shot) => one_shot,
+        None => return false
+    };
+    let scene = ctx.prepare_scene(item);
+    let image = ctx.backend.render_to_buffer(scene);
+    match image.save_png(&one_shot.output) {
+        Ok(()) => info!("wrote {:?}", one_shot.output),
+        Err(e) => error!("failed to write {:?}: {}", one_shot.output, e),
+    }
+    ctx.close();
+    true
 }
+#[cfg(not(feature="png"))]
+fn render_one_shot<T: Interactive>(_item: &mut T, _ctx: &mut Context) -> bool {
+    false
+}
+
+// the scene has to come from the item on this thread, pathfinder builds it on its thread pool
+fn start_thumbnail<T: Interactive>(item: &mut T, ctx: &mut Context, page: usize) -> (usize, Vector2I) {
+    let size = ctx.config.thumbnail_size;
+    let scene = ctx.page_or_item_scene(item, page);
+    let scene = item.transform_scene(ctx, scene);
+    ctx.backend.window.start_thumbnail(scene, size, ctx.config.thumbnail_supersample);
+    (page, size)
+}
+fn finish_thumbnail(ctx: &mut Context, (page, size): (usize, Vector2I)) {
+    if let Some(image) = ctx.backend.window.finish_thumbnail() {
+        ctx.thumbnails.insert((page, (size.x(), size.y())), image);
+    }
+}
+
 fn env_vec(name: &str) -> Option<Vector2F> {
     use tuple::{T2, Map, TupleElements};
     let val = std::env::var(name).ok()?;
@@ -59,7 +398,7 @@ fn env_vec(name: &str) -> Option<Vector2F> {
 }
 
 #[cfg(not(target_arch="wasm32"))]
-pub fn show<T: Interactive>(mut item: T, config: Config) {
+pub fn show<T: Interactive>(mut item: T, mut config: Config) {
     use winit::{event_loop::EventLoopBuilder, event::{KeyEvent, Modifiers}};
 
     info!("creating event loop");
@@ -69,14 +408,32 @@ pub fn show<T: Interactive>(mut item: T, config: Config) {
     // EventLoop::<<T as Interactive>::Event>::with_user_event();
 
     let mut cursor_pos = Vector2F::default();
-    let mut dragging = false;
 
     let window_size = item.window_size_hint().unwrap_or(vec2f(600., 400.));
-    let window = crate::gl::GlWindow::new(&event_loop, item.title(), window_size, &config);
+    if config.min_window_size.is_none() {
+        config.min_window_size = item.min_window_size_hint();
+    }
+    if config.deterministic {
+        // rayon changes the order tiles are built in, and D3D11 bins them with atomics on the GPU
+        config.threads = false;
+        config.render_level = RendererLevel::D3D9;
+    }
+    let title = config.title.clone().unwrap_or_else(|| item.title());
+    let window = match crate::gl::GlWindow::new(&event_loop, title, window_size, &config) {
+        Ok(window) => window,
+        Err(e) => {
+            item.on_init_error(&e);
+            return;
+        }
+    };
     let backend = Backend::new(window);
     let mut ctx = Context::new(config, backend);
     let scale_factor = ctx.backend.window.scale_factor();
     ctx.set_scale_factor(scale_factor);
+    ctx.theme = ctx.backend.window.window().theme();
+    if ctx.config.cursor_grab != CursorGrabMode::None {
+        ctx.backend.set_cursor_grab(ctx.config.cursor_grab);
+    }
     ctx.request_redraw();
     ctx.window_size = window_size;
 
@@ -85,33 +442,149 @@ pub fn show<T: Interactive>(mut item: T, config: Config) {
 
     item.init(&mut ctx, Emitter(proxy));
 
+    #[cfg(feature="png")]
+    if let Some(ref one_shot) = ctx.config.one_shot {
+        let (page, scale) = (one_shot.page, one_shot.scale);
+        ctx.goto_page(page);
+        ctx.set_zoom(scale);
+    }
+
     let mut modifiers = ModifiersState::default();
-    info!("entering the event loop");
-    event_loop.run_return(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Wait;
+    let mut last_frame: Option<Instant> = None;
+    // when the next `update_interval` tick and redraw are due
+    let mut next_update: Option<Instant> = None;
+    // a redraw has been requested from the window and did not happen yet
+    let mut redraw_pending = false;
+    let mut last_cursor_move = Instant::now();
+    let mut resized_since_frame = false;
+    // page and size of the thumbnail being built
+    let mut thumbnail_in_flight: Option<(usize, Vector2I)> = None;
+    // direction and time of the last step while a zoom key is held, with `Config::smooth_key_zoom`
+    let mut key_zoom: Option<(f32, Instant)> = None;
+    #[cfg(feature="gamepad")]
+    let mut gamepads = crate::gamepad::Gamepads::new();
+    #[cfg(feature="gamepad")]
+    let mut gamepad_active = false;
+    let catch_panics = ctx.config.catch_panics;
+    let mut handle_event = move |event: Event<'_, T::Event>, control_flow: &mut ControlFlow| {
+        *control_flow = match ctx.config.loop_mode {
+            LoopMode::Wait => ControlFlow::Wait,
+            LoopMode::Poll => ControlFlow::Poll,
+            LoopMode::WaitUntil(dt) => ControlFlow::WaitUntil(Instant::now() + dt),
+        };
         match event {
             Event::NewEvents(StartCause::Init) => {
             }
-            Event::NewEvents(StartCause::ResumeTimeReached { start: _, requested_resume: _ }) => {
-                ctx.request_redraw();
+            Event::RedrawRequested(_) if ctx.minimized => {
+                redraw_pending = false;
             }
             Event::RedrawRequested(_) => {
-                let options = BuildOptions {
-                    transform: RenderTransform::default(),
-                    dilation: Vector2F::default(),
-                    subpixel_aa_enabled: false
-                };
+                redraw_pending = false;
+                let options = ctx.backend.window.build_options();
 
+                // the window manager may not have used the size we asked for
+                if last_frame.is_none() && ctx.config.fit_on_first_frame {
+                    let size = ctx.backend.window.window().inner_size();
+                    ctx.window_size = Vector2F::new(size.width as f32, size.height as f32);
+                    ctx.fit_first_frame();
+                }
                 ctx.backend.window.resized(ctx.window_size);
-                let scene = item.scene(&mut ctx);
-                ctx.backend.window.render(scene, options);
+                if render_one_shot(&mut item, &mut ctx) {
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
+                // split viewports are always drawn in one go
+                let progressive = ctx.config.progressive && ctx.viewports.len() == 1;
+                // keep working on the current scene unless something changed
+                let new_scene = ctx.redraw_requested || !progressive || !ctx.backend.window.progressive_pending();
+                if new_scene && ctx.viewports.len() > 1 {
+                    let background = item.background(&ctx).unwrap_or(ctx.config.background);
+                    ctx.backend.window.set_background(background);
+                    let scenes = ctx.prepare_viewport_scenes(&mut item);
+                    ctx.backend.window.render_regions(scenes, options);
+                } else if new_scene {
+                    let background = item.background(&ctx).unwrap_or(ctx.config.background);
+                    let cache_key = ctx.render_cache_key(background).filter(|_| !progressive);
+                    match cache_key {
+                        Some(key) if ctx.backend.window.present_cached(&key) => {}
+                        _ => {
+                            let scene = ctx.prepare_scene(&mut item);
+                            if progressive {
+                                ctx.backend.window.render_progressive(scene);
+                            } else if let Some(key) = cache_key {
+                                ctx.backend.window.render_cached(scene, options, key);
+                            } else {
+                                ctx.backend.window.render(scene, options);
+                            }
+                        }
+                    }
+                }
+                if progressive && ctx.backend.window.render_next_band() {
+                    ctx.backend.window.request_redraw();
+                    redraw_pending = true;
+                }
                 ctx.redraw_requested = false;
+                last_frame = Some(Instant::now());
+                resized_since_frame = false;
+                ctx.frame_rendered = true;
             },
+            Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta: (dx, dy) }, .. } => {
+                item.raw_mouse_motion(&mut ctx, Vector2F::new(dx as f32, dy as f32));
+            }
             Event::UserEvent(e) => {
                 item.event(&mut ctx, e);
             }
-            Event::MainEventsCleared => item.idle(&mut ctx),
+            // `RedrawRequested` comes after `MainEventsCleared`, so a frame is only known here
+            Event::RedrawEventsCleared if ctx.config.idle_policy == IdlePolicy::PerFrame => {
+                if ctx.take_idle() {
+                    item.idle(&mut ctx);
+                }
+            }
+            Event::MainEventsCleared => {
+                // checked on every pass, `ResumeTimeReached` never comes with `LoopMode::Poll`
+                // and other wake ups (redraw timers, gamepads, playback) must not tick early
+                match ctx.update_interval {
+                    Some(dt) => {
+                        let now = Instant::now();
+                        if next_update.map_or(true, |t| now >= t) {
+                            let dt = Duration::from_secs_f32(dt);
+                            let dt = ctx.min_frame_duration().map_or(dt, |min| dt.max(min));
+                            next_update = Some(now + dt);
+                            item.tick(&mut ctx, now);
+                            ctx.request_redraw();
+                        }
+                    }
+                    None => next_update = None,
+                }
+                ctx.flush_events(&mut item, &mut cursor_pos);
+                ctx.dispatch_queued_events(&mut item);
+                if ctx.take_idle() {
+                    item.idle(&mut ctx);
+                }
+
+                #[cfg(feature="gamepad")]
+                if let Some(ref mut gamepads) = gamepads {
+                    gamepad_active = gamepads.poll(&mut item, &mut ctx);
+                }
+
+                // one thumbnail per pass, so input stays responsive: it is built while the events
+                // of the next pass are handled, then drawn. redraw once the batch is done.
+                if let Some(thumbnail) = thumbnail_in_flight.take() {
+                    finish_thumbnail(&mut ctx, thumbnail);
+                    if ctx.pending_thumbnails.is_empty() {
+                        ctx.request_redraw();
+                    }
+                }
+                if let Some(page) = ctx.pending_thumbnails.pop() {
+                    thumbnail_in_flight = Some(start_thumbnail(&mut item, &mut ctx, page));
+                }
+            }
             Event::WindowEvent { event, .. } => {
+                // everything else sees the latest cursor position and window size
+                match event {
+                    WindowEvent::CursorMoved { .. } | WindowEvent::Resized(_) => {}
+                    _ => ctx.flush_events(&mut item, &mut cursor_pos)
+                }
                 match event {
                     WindowEvent::ScaleFactorChanged { scale_factor, new_inner_size: PhysicalSize { width, height } } => {
                         ctx.set_scale_factor(scale_factor as f32);
@@ -120,72 +593,212 @@ pub fn show<T: Interactive>(mut item: T, config: Config) {
                         *height = ctx.window_size.y().ceil() as u32;
                         ctx.request_redraw();
                     }
-                    WindowEvent::Focused { ..} => ctx.request_redraw(),
+                    WindowEvent::Focused(focused) => {
+                        // the key release goes elsewhere
+                        if !focused {
+                            key_zoom = None;
+                        }
+                        if ctx.config.redraw_on_focus {
+                            ctx.request_redraw();
+                        }
+                    }
+                    WindowEvent::ThemeChanged(theme) => {
+                        ctx.theme = Some(theme);
+                        item.theme_changed(&mut ctx, theme);
+                    }
                     WindowEvent::Resized(PhysicalSize {width, height}) => {
                         let physical_size = Vector2F::new(width as f32, height as f32);
-                        ctx.window_size = physical_size;
-                        ctx.check_bounds();
-                        ctx.request_redraw();
+                        resized_since_frame = true;
+                        if ctx.config.coalesce_events {
+                            ctx.pending_resize = Some(physical_size);
+                        } else {
+                            window_resized(&mut ctx, physical_size);
+                        }
                     }
                     WindowEvent::ModifiersChanged(new_modifiers) => {
                         modifiers = new_modifiers.state();
+                        if !modifiers.control_key() {
+                            key_zoom = None;
+                        }
                     }
                     WindowEvent::KeyboardInput { event, ..  } => {
-                        item.keyboard_input(&mut ctx, modifiers, event);
+                        // typed characters go to `text_input`, like on the web
+                        let text = match (event.state, &event.text) {
+                            (WinitElementState::Pressed, Some(text)) => text.chars().filter(|c| !c.is_control()).collect(),
+                            _ => String::new()
+                        };
+                        let copy_image = event.state == WinitElementState::Pressed
+                            && modifiers.control_key() && modifiers.shift_key()
+                            && ctx.config.copy_image_key == Some(event.physical_key);
+                        let toggle_debug_ui = event.state == WinitElementState::Pressed
+                            && ctx.config.debug_ui_key == Some(event.physical_key);
+                        let paste = event.state == WinitElementState::Pressed
+                            && modifiers.control_key() && !modifiers.shift_key()
+                            && event.physical_key == KeyCode::KeyV;
+                        // the keys of the default `keyboard_input`
+                        let zoom_direction = match event.physical_key {
+                            KeyCode::Digit1 => 1.0,
+                            KeyCode::Digit2 => -1.0,
+                            _ => 0.0,
+                        };
+                        let smooth_zoom = ctx.config.smooth_key_zoom && ctx.config.zoom && zoom_direction != 0.0
+                            && (modifiers.control_key() || key_zoom.is_some());
+                        if copy_image {
+                            copy_view_to_clipboard(&mut item, &mut ctx);
+                        } else if toggle_debug_ui {
+                            ctx.toggle_debug_ui();
+                        } else if smooth_zoom {
+                            key_zoom = match (event.state, key_zoom) {
+                                (WinitElementState::Released, _) => None,
+                                (_, Some(held)) => Some(held), // key repeat
+                                (_, None) => Some((zoom_direction, Instant::now())),
+                            };
+                        } else {
+                            let raw_key = RawKey {
+                                physical_key: event.physical_key,
+                                scancode: event.physical_key.to_scancode(),
+                                state: event.state,
+                                modifiers,
+                            };
+                            item.raw_key_input(&mut ctx, raw_key);
+                            item.keyboard_input(&mut ctx, modifiers, event);
+                            if !text.is_empty() {
+                                item.text_input(&mut ctx, text);
+                            }
+                            if paste {
+                                paste_image_from_clipboard(&mut item, &mut ctx);
+                            }
+                        }
                     }
                     WindowEvent::CursorMoved { position: PhysicalPosition { x, y }, .. } => {
                         let new_pos = Vector2F::new(x as f32, y as f32);
-                        let cursor_delta = new_pos - cursor_pos;
-                        cursor_pos = new_pos;
-
-                        if dragging {
-                            ctx.move_by(cursor_delta * (-1.0 / ctx.scale));
+                        last_cursor_move = Instant::now();
+                        if ctx.config.coalesce_events {
+                            ctx.pending_cursor = Some(new_pos);
                         } else {
-                            item.cursor_moved(&mut ctx, new_pos);
+                            cursor_moved(&mut item, &mut ctx, &mut cursor_pos, new_pos);
                         }
                     },
-                    WindowEvent::MouseInput { button: MouseButton::Left, state, .. } => {
+                    WindowEvent::CursorLeft { .. } => {
+                        ctx.cursor_pos = None;
+                    }
+                    WindowEvent::MouseInput { button, state, .. } if button == primary_button(&ctx.config) => {
                         match (state, modifiers.shift_key()) {
-                            (WinitElementState::Pressed, true) if ctx.config.pan => dragging = true,
-                            (WinitElementState::Released, _) if dragging => dragging = false,
+                            (WinitElementState::Pressed, true) if ctx.config.pan => ctx.dragging = true,
+                            (WinitElementState::Released, _) if ctx.dragging => ctx.dragging = false,
                             _ => {
+                                if ctx.config.text_selection {
+                                    match state {
+                                        WinitElementState::Pressed => ctx.begin_selection(ctx.view_transform().inverse() * cursor_pos),
+                                        WinitElementState::Released => ctx.end_selection(),
+                                    }
+                                }
                                 let page_nr = ctx.page_nr;
-                                item.mouse_input(&mut ctx, page_nr, cursor_pos, state);
+                                item.mouse_input(&mut ctx, page_nr, cursor_pos, state, modifiers);
+                                ctx.pointer_down = state == WinitElementState::Pressed;
+                                item.pointer_input(&mut ctx, page_nr, cursor_pos, 1.0, state);
                             }
                         }
                     }
+                    WindowEvent::Touch(Touch { phase, location: PhysicalPosition { x, y }, force, .. }) => {
+                        let state = match phase {
+                            TouchPhase::Started | TouchPhase::Moved => WinitElementState::Pressed,
+                            TouchPhase::Ended | TouchPhase::Cancelled => WinitElementState::Released,
+                        };
+                        let pressure = force.map_or(1.0, |f| f.normalized() as f32);
+                        let page_nr = ctx.page_nr;
+                        item.pointer_input(&mut ctx, page_nr, Vector2F::new(x as f32, y as f32), pressure, state);
+                    }
                     WindowEvent::MouseWheel { delta, .. } => {
                         let delta = match delta {
-                            MouseScrollDelta::PixelDelta(PhysicalPosition { x: dx, y: dy }) => Vector2F::new(dx as f32, dy as f32) * ctx.pixel_scroll_factor,
-                            MouseScrollDelta::LineDelta(dx, dy) => Vector2F::new(dx as f32, dy as f32) * ctx.line_scroll_factor,
+                            MouseScrollDelta::PixelDelta(PhysicalPosition { x: dx, y: dy }) => ctx.scroll_pixels(Vector2F::new(dx as f32, dy as f32)),
+                            MouseScrollDelta::LineDelta(dx, dy) => ctx.scroll_lines(Vector2F::new(dx as f32, dy as f32)),
                         };
                         if ctx.config.zoom && modifiers.control_key() {
-                            ctx.zoom_by(-0.02 * delta.y());
+                            let log2_factor = ctx.wheel_zoom(delta.y());
+                            ctx.zoom_focused(log2_factor);
                         } else if ctx.config.pan {
-                            ctx.move_by(delta * (-1.0 / ctx.scale));
+                            ctx.move_by(delta * (-ctx.scale_factor / ctx.scale));
                         }
                     }
                     WindowEvent::CloseRequested => {
-                        println!("The close button was pressed; stopping");
-                        ctx.close();
+                        if item.should_close(&mut ctx) {
+                            println!("The close button was pressed; stopping");
+                            ctx.close();
+                        }
                     },
                     _ => {}
                 }
             }
             Event::LoopDestroyed => {
                 item.exit(&mut ctx);
+                item.release_gpu_resources(&mut ctx);
             }
             _ => {}
         }
-        if ctx.redraw_requested {
-            ctx.backend.window.request_redraw();
+        if let Some((direction, ref mut last_step)) = key_zoom {
+            let now = Instant::now();
+            ctx.zoom_by(direction * KEY_ZOOM_RATE * (now - *last_step).as_secs_f32());
+            *last_step = now;
+            wake_up_at(control_flow, now + KEY_ZOOM_INTERVAL);
+        }
+        ctx.dispatch_notifications(&mut item);
+        if let Some(dt) = ctx.check_redraw_timer() {
+            wake_up_at(control_flow, Instant::now() + dt);
+        }
+        let playing = ctx.update_track();
+        let min_frame = ctx.min_frame_duration();
+        // the last size is drawn once the interval is over
+        let min_redraw = match (resized_since_frame, ctx.config.resize_frame_interval) {
+            (true, Some(interval)) => Some(min_frame.map_or(interval, |min| min.max(interval))),
+            _ => min_frame,
+        };
+        // the request stays until the window is restored
+        if ctx.redraw_requested && !redraw_pending && !ctx.minimized {
+            match (min_redraw, last_frame) {
+                // too early, come back when the next frame is due
+                (Some(min), Some(last)) if last.elapsed() < min => {
+                    *control_flow = ControlFlow::WaitUntil(last + min);
+                }
+                _ => {
+                    ctx.backend.window.request_redraw();
+                    redraw_pending = true;
+                }
+            }
         }
         
-        if let Some(dt) = ctx.update_interval {
-            *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_secs_f32(dt));
+        if let Some(t) = next_update {
+            wake_up_at(control_flow, t);
+        }
+        if ctx.presentation_mode() && !ctx.cursor_hidden {
+            let hide_at = last_cursor_move + crate::PRESENTATION_CURSOR_TIMEOUT;
+            if Instant::now() >= hide_at {
+                ctx.set_cursor_hidden(true);
+            } else {
+                wake_up_at(control_flow, hide_at);
+            }
+        }
+        #[cfg(feature="gamepad")]
+        if gamepad_active {
+            *control_flow = ControlFlow::Poll;
+        } else if gamepads.as_ref().map_or(false, |g| g.any_connected()) {
+            wake_up_at(control_flow, Instant::now() + crate::gamepad::POLL_INTERVAL);
+        }
+        if playing {
+            // come back for the next frame of the track instead of spinning in between
+            let interval = min_frame.unwrap_or(PLAYBACK_INTERVAL);
+            wake_up_at(control_flow, last_frame.unwrap_or_else(Instant::now) + interval);
+        }
+        if !ctx.queued_events.is_empty() || thumbnail_in_flight.is_some() {
+            *control_flow = ControlFlow::Poll;
         }
         if ctx.close {
             *control_flow = ControlFlow::Exit;
         }
+    };
+    info!("entering the event loop");
+    event_loop.run_return(move |event, _, control_flow| {
+        // the event that panicked is lost, the next one is handled as usual
+        catch_panic(catch_panics, || handle_event(event, control_flow));
     });
 }
\ No newline at end of file
@@ -1,8 +1,269 @@
 use pathfinder_geometry::vector::Vector2I;
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::vector::Vector2F;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_content::{outline::{Outline, Contour}, effects::BlendMode};
+use pathfinder_color::{ColorU, ColorF};
+use pathfinder_renderer::{scene::{Scene, DrawPath}, paint::{Paint, PaintId}, gpu::options::RendererLevel};
+use pathfinder_resources::ResourceLoader;
+use crate::{GridSpec, InitError};
 
-fn round_to_16(i: i32) -> i32 {
-    (i + 15) & !0xf
+// pathfinder does not like scene sizes that are not a multiple of the tile size
+pub fn tile_size(level: RendererLevel) -> Vector2I {
+    match level {
+        RendererLevel::D3D9 | RendererLevel::D3D11 => Vector2I::splat(16),
+    }
 }
-pub fn round_v_to_16(v: Vector2I) -> Vector2I {
-    Vector2I::new(round_to_16(v.x()), round_to_16(v.y()))
+
+// a few of the files pathfinder loads when creating a renderer.
+// it panics if they are missing, this turns that into an error.
+pub fn check_resources(loader: &dyn ResourceLoader, level: RendererLevel) -> Result<(), InitError> {
+    let shader_dir = match level {
+        RendererLevel::D3D9 => "gl3",
+        RendererLevel::D3D11 => "gl4",
+    };
+    let paths = [
+        "textures/area-lut.png".to_string(),
+        "textures/gamma-lut.png".to_string(),
+        format!("shaders/{}/blit.vs.glsl", shader_dir),
+        format!("shaders/{}/blit.fs.glsl", shader_dir),
+    ];
+    for path in paths.iter() {
+        if let Err(error) = loader.slurp(path) {
+            return Err(InitError::MissingResource { path: path.clone(), error });
+        }
+    }
+    Ok(())
+}
+
+fn round_to_tile(i: i32, tile: i32) -> i32 {
+    (i + tile - 1) / tile * tile
+}
+pub fn round_v_to_tile(v: Vector2I, tile: Vector2I) -> Vector2I {
+    Vector2I::new(round_to_tile(v.x(), tile.x()), round_to_tile(v.y(), tile.y()))
+}
+
+// view box for rendering `size` pixels into a framebuffer of `padded` size.
+// `exact` keeps the view box at the requested size instead of the padded one.
+pub fn render_view_box(size: Vector2I, padded: Vector2I, exact: bool) -> RectF {
+    RectF::new(Vector2F::default(), if exact { size.to_f32() } else { padded.to_f32() })
+}
+
+// a white rectangle in difference mode inverts everything below it
+pub fn invert(scene: &mut Scene, area: RectF) {
+    let mut outline = Outline::new();
+    outline.push_contour(Contour::from_rect(area));
+    let paint = scene.push_paint(&Paint::from_color(ColorU::white()));
+    let mut path = DrawPath::new(outline, paint);
+    path.set_blend_mode(BlendMode::Difference);
+    scene.push_draw_path(path);
+}
+
+const CHECKER_SIZE: f32 = 8.0;
+
+// light and dark squares of a fixed pixel size covering `area`, to show what is transparent
+pub fn checkerboard(scene: &mut Scene, area: RectF) {
+    let light = scene.push_paint(&Paint::from_color(ColorU::new(255, 255, 255, 255)));
+    let dark = scene.push_paint(&Paint::from_color(ColorU::new(204, 204, 204, 255)));
+    fill_rect(scene, area, light);
+
+    let mut outline = Outline::new();
+    let x_range = (area.min_x() / CHECKER_SIZE).floor() as i64 .. (area.max_x() / CHECKER_SIZE).ceil() as i64;
+    let y_range = (area.min_y() / CHECKER_SIZE).floor() as i64 .. (area.max_y() / CHECKER_SIZE).ceil() as i64;
+    for y in y_range {
+        for x in x_range.clone() {
+            if (x + y) % 2 != 0 {
+                let origin = Vector2F::new(x as f32, y as f32) * CHECKER_SIZE;
+                outline.push_contour(Contour::from_rect(RectF::new(origin, Vector2F::splat(CHECKER_SIZE))));
+            }
+        }
+    }
+    scene.push_draw_path(DrawPath::new(outline, dark));
+}
+
+// something of everything a frame usually has: solid and partially covered tiles, transparency
+pub fn prewarm_scene(area: RectF) -> Scene {
+    let mut scene = Scene::new();
+    scene.set_view_box(area);
+    let solid = scene.push_paint(&Paint::from_color(ColorU::new(255, 255, 255, 255)));
+    let translucent = scene.push_paint(&Paint::from_color(ColorU::new(0, 0, 0, 128)));
+    fill_rect(&mut scene, area, solid);
+    fill_rect(&mut scene, area.contract(Vector2F::splat(area.width().min(area.height()) * 0.3)), translucent);
+    scene
+}
+
+// shown instead of a scene that could not be built: pale red with a red frame
+pub fn error_scene(area: RectF) -> Scene {
+    let mut scene = Scene::new();
+    scene.set_view_box(area);
+    let border = scene.push_paint(&Paint::from_color(ColorU::new(200, 0, 0, 255)));
+    let fill = scene.push_paint(&Paint::from_color(ColorU::new(255, 220, 220, 255)));
+    fill_rect(&mut scene, area, border);
+    fill_rect(&mut scene, area.contract(Vector2F::splat(8.0)), fill);
+    scene
+}
+
+// cover everything in `area` outside of `viewport` with `color`
+pub fn letterbox(scene: &mut Scene, viewport: RectF, area: RectF, color: ColorF) {
+    let paint = scene.push_paint(&Paint::from_color(color.to_u8()));
+    let margins = [
+        RectF::from_points(area.origin(), Vector2F::new(area.max_x(), viewport.min_y())),
+        RectF::from_points(Vector2F::new(area.min_x(), viewport.max_y()), area.lower_right()),
+        RectF::from_points(Vector2F::new(area.min_x(), viewport.min_y()), Vector2F::new(viewport.min_x(), viewport.max_y())),
+        RectF::from_points(Vector2F::new(viewport.max_x(), viewport.min_y()), Vector2F::new(area.max_x(), viewport.max_y())),
+    ];
+    for &rect in margins.iter() {
+        if rect.width() > 0.0 && rect.height() > 0.0 {
+            fill_rect(scene, rect, paint);
+        }
+    }
+}
+
+// selection rectangles in scene units, `view` maps them into the coordinates of `scene`
+pub fn highlight(scene: &mut Scene, rects: &[RectF], view: Transform2F) {
+    let mut outline = Outline::new();
+    for &rect in rects {
+        outline.push_contour(Contour::from_rect(view * rect));
+    }
+    let paint = scene.push_paint(&Paint::from_color(ColorU::new(170, 200, 255, 255)));
+    let mut path = DrawPath::new(outline, paint);
+    path.set_blend_mode(BlendMode::Multiply);
+    scene.push_draw_path(path);
+}
+
+// scale everything below by `alpha`, colors included as the framebuffer is premultiplied
+pub fn fade(scene: &mut Scene, area: RectF, alpha: f32) {
+    let mut outline = Outline::new();
+    outline.push_contour(Contour::from_rect(area));
+    let paint = scene.push_paint(&Paint::from_color(ColorU::new(0, 0, 0, (alpha * 255.0).round() as u8)));
+    let mut path = DrawPath::new(outline, paint);
+    path.set_blend_mode(BlendMode::DestIn);
+    scene.push_draw_path(path);
+}
+
+fn fill_rect(scene: &mut Scene, rect: RectF, paint: PaintId) {
+    let mut outline = Outline::new();
+    outline.push_contour(Contour::from_rect(rect));
+    scene.push_draw_path(DrawPath::new(outline, paint));
+}
+
+const RULER_WIDTH: f32 = 16.0;
+
+// `view` maps scene units into the coordinates of `scene`, `area` is the visible part of it.
+// lines are one pixel wide, at any zoom.
+pub fn grid(scene: &mut Scene, spec: &GridSpec, view: Transform2F, area: RectF) {
+    let step = spec.spacing / spec.subdivisions.max(1) as f32;
+    let major_every = spec.subdivisions.max(1) as i64;
+    let pixels_per_step = step * view.extend_scale_factor();
+    if pixels_per_step.is_nan() || pixels_per_step <= 0.0 {
+        return;
+    }
+    // skip the subdivisions when they get too dense
+    let (step, major_every) = if pixels_per_step < 4.0 { (spec.spacing, 1) } else { (step, major_every) };
+    if step * view.extend_scale_factor() < 4.0 {
+        return;
+    }
+
+    let mut minor_color = spec.color;
+    minor_color.a /= 2;
+    let major = scene.push_paint(&Paint::from_color(spec.color));
+    let minor = scene.push_paint(&Paint::from_color(minor_color));
+    let ruler = scene.push_paint(&Paint::from_color(ColorU::new(240, 240, 240, 220)));
+
+    let visible = view.inverse() * area;
+    let x_range = (visible.min_x() / step).floor() as i64 ..= (visible.max_x() / step).ceil() as i64;
+    let y_range = (visible.min_y() / step).floor() as i64 ..= (visible.max_y() / step).ceil() as i64;
+
+    for i in x_range.clone() {
+        let x = (view * Vector2F::new(i as f32 * step, 0.0)).x().round();
+        let paint = if i % major_every == 0 { major } else { minor };
+        fill_rect(scene, RectF::from_points(Vector2F::new(x, area.min_y()), Vector2F::new(x + 1.0, area.max_y())), paint);
+    }
+    for i in y_range.clone() {
+        let y = (view * Vector2F::new(0.0, i as f32 * step)).y().round();
+        let paint = if i % major_every == 0 { major } else { minor };
+        fill_rect(scene, RectF::from_points(Vector2F::new(area.min_x(), y), Vector2F::new(area.max_x(), y + 1.0)), paint);
+    }
+
+    // rulers with long ticks and the scene coordinate at the major lines
+    let top = RectF::from_points(area.origin(), Vector2F::new(area.max_x(), area.min_y() + RULER_WIDTH));
+    let left = RectF::from_points(area.origin(), Vector2F::new(area.min_x() + RULER_WIDTH, area.max_y()));
+    fill_rect(scene, top, ruler);
+    fill_rect(scene, left, ruler);
+    let major_pixels = step * major_every as f32 * view.extend_scale_factor();
+    let text = |i: i64| {
+        let text = format!("{:.3}", i as f32 * step);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    };
+    for i in x_range {
+        let x = (view * Vector2F::new(i as f32 * step, 0.0)).x().round();
+        let len = if i % major_every == 0 { RULER_WIDTH } else { RULER_WIDTH * 0.4 };
+        fill_rect(scene, RectF::from_points(Vector2F::new(x, top.max_y() - len), Vector2F::new(x + 1.0, top.max_y())), major);
+        if i % major_every == 0 {
+            let text = text(i);
+            if label_size(&text, false).x() + 4.0 < major_pixels {
+                label(scene, &text, Vector2F::new(x + 3.0, top.min_y() + 3.0), false, major);
+            }
+        }
+    }
+    for i in y_range {
+        let y = (view * Vector2F::new(0.0, i as f32 * step)).y().round();
+        let len = if i % major_every == 0 { RULER_WIDTH } else { RULER_WIDTH * 0.4 };
+        fill_rect(scene, RectF::from_points(Vector2F::new(left.max_x() - len, y), Vector2F::new(left.max_x(), y + 1.0)), major);
+        if i % major_every == 0 {
+            let text = text(i);
+            if label_size(&text, true).y() + 4.0 < major_pixels {
+                label(scene, &text, Vector2F::new(left.min_x() + 3.0, y + 3.0), true, major);
+            }
+        }
+    }
+}
+
+// 3x5 pixel glyphs for the ruler labels, one row per entry, 0b100 is the left column.
+// there is no font to draw text with, and coordinates only need these.
+const GLYPHS: [(char, [u8; 5]); 12] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+];
+const GLYPH_PIXEL: f32 = 2.0;
+
+// offset from one glyph to the next, `vertical` stacks them to fit into the left ruler
+fn glyph_advance(vertical: bool) -> Vector2F {
+    match vertical {
+        false => Vector2F::new(4.0 * GLYPH_PIXEL, 0.0),
+        true => Vector2F::new(0.0, 6.0 * GLYPH_PIXEL),
+    }
+}
+fn label_size(text: &str, vertical: bool) -> Vector2F {
+    let glyphs = text.chars().count().max(1);
+    glyph_advance(vertical) * (glyphs - 1) as f32 + Vector2F::new(3.0, 5.0) * GLYPH_PIXEL
+}
+fn label(scene: &mut Scene, text: &str, origin: Vector2F, vertical: bool, paint: PaintId) {
+    let mut outline = Outline::new();
+    for (n, c) in text.chars().enumerate() {
+        let rows = match GLYPHS.iter().find(|&&(g, _)| g == c) {
+            Some(&(_, rows)) => rows,
+            None => continue,
+        };
+        let glyph_origin = origin + glyph_advance(vertical) * n as f32;
+        for (y, row) in rows.iter().enumerate() {
+            for x in 0 .. 3 {
+                if row & (0b100 >> x) != 0 {
+                    let pixel = glyph_origin + Vector2F::new(x as f32, y as f32) * GLYPH_PIXEL;
+                    outline.push_contour(Contour::from_rect(RectF::new(pixel, Vector2F::splat(GLYPH_PIXEL))));
+                }
+            }
+        }
+    }
+    scene.push_draw_path(DrawPath::new(outline, paint));
 }
@@ -1,13 +1,27 @@
 use pathfinder_geometry::vector::{Vector2F};
 use pathfinder_renderer::scene::Scene;
-pub use winit::{event::{ElementState, KeyEvent}, keyboard::{ModifiersState, KeyCode}};
+use pathfinder_color::ColorF;
+pub use winit::{event::{ElementState, KeyEvent}, keyboard::{ModifiersState, KeyCode}, window::{Theme, CursorGrabMode}};
 use std::fmt::Debug;
+use std::any::Any;
 use crate::*;
 
 pub trait Interactive: 'static {
     type Event: Debug + Send + 'static;
 
     fn scene(&mut self, ctx: &mut Context) -> Scene;
+    // the scene of any page without changing the current one, i.e. for thumbnails.
+    // the default makes `page` the current page while `scene` runs.
+    fn scene_for_page(&mut self, ctx: &mut Context, page: usize) -> Scene {
+        let current_page = ctx.page_nr;
+        ctx.page_nr = page;
+        let scene = self.scene(ctx);
+        ctx.page_nr = current_page;
+        scene
+    }
+
+    // applied to the result of `scene` before rendering, i.e. for wrapping another item
+    fn transform_scene(&mut self, ctx: &mut Context, scene: Scene) -> Scene { scene }
 
     fn char_input(&mut self, ctx: &mut Context, input: char) {}
     fn text_input(&mut self, ctx: &mut Context, input: String) {
@@ -15,24 +29,83 @@ pub trait Interactive: 'static {
             self.char_input(ctx, c);
         }
     }
+    // an image was pasted. native windows check the clipboard on Ctrl+V (with the clipboard feature),
+    // on the web the page passes it to `WasmView::image_pasted`.
+    fn image_pasted(&mut self, ctx: &mut Context, image: RawImage) {}
     fn keyboard_input(&mut self, ctx: &mut Context, modifiers: ModifiersState, event: KeyEvent) {
         match (event.state, modifiers.control_key(), event.physical_key) {
             (ElementState::Pressed, false, KeyCode::PageDown) => ctx.next_page(),
             (ElementState::Pressed, false, KeyCode::PageUp) => ctx.prev_page(),
-            (ElementState::Pressed, true, KeyCode::Digit1) => ctx.zoom_by(0.2),
-            (ElementState::Pressed, true, KeyCode::Digit2) => ctx.zoom_by(-0.2),
-            (ElementState::Pressed, true, KeyCode::Digit0) => ctx.set_zoom(DEFAULT_SCALE),
+            (ElementState::Pressed, false, KeyCode::ArrowRight | KeyCode::ArrowDown) if ctx.presentation_mode() => ctx.next_page(),
+            (ElementState::Pressed, false, KeyCode::ArrowLeft | KeyCode::ArrowUp) if ctx.presentation_mode() => ctx.prev_page(),
+            (ElementState::Pressed, true, KeyCode::Digit1) => ctx.zoom_by(ctx.config.zoom_step),
+            (ElementState::Pressed, true, KeyCode::Digit2) => ctx.zoom_by(-ctx.config.zoom_step),
+            (ElementState::Pressed, true, KeyCode::Digit0) => ctx.reset_view(),
             _ => return
         }
     }
-    fn mouse_input(&mut self, ctx: &mut Context, page: usize, pos: Vector2F, state: ElementState) {}
+    // every key press and release by its position on the keyboard, before `keyboard_input`.
+    // use this for layout independent bindings, i.e. WASD on an AZERTY keyboard.
+    fn raw_key_input(&mut self, ctx: &mut Context, key: RawKey) {}
+    fn mouse_input(&mut self, ctx: &mut Context, page: usize, pos: Vector2F, state: ElementState, modifiers: ModifiersState) {}
     fn cursor_moved(&mut self, ctx: &mut Context, pos: Vector2F) {}
+    // relative movement of the mouse in device units, also when the cursor can not move any further,
+    // i.e. for orbit controls with `Context::set_cursor_grab(CursorGrabMode::Locked)`
+    fn raw_mouse_motion(&mut self, ctx: &mut Context, delta: Vector2F) {}
+    // like `mouse_input` for pens and touch, with `pressure` from 0 to 1. mice report 1.0.
+    // called on press, on every move while pressed (with `Pressed` again) and on release.
+    fn pointer_input(&mut self, ctx: &mut Context, page: usize, pos: Vector2F, pressure: f32, state: ElementState) {}
     fn exit(&mut self, ctx: &mut Context) {}
+    // called after `exit` while the GL context is still current. delete textures and buffers here,
+    // once the item is dropped the context may be gone already.
+    fn release_gpu_resources(&mut self, ctx: &mut Context) {}
+    // the user wants to close the window (or leave the page). return false to keep it open,
+    // i.e. to ask about unsaved changes first.
+    fn should_close(&mut self, ctx: &mut Context) -> bool { true }
+    fn theme_changed(&mut self, ctx: &mut Context, theme: Theme) {}
+    // the current page changed from `old` to `new`
+    fn page_changed(&mut self, ctx: &mut Context, old: usize, new: usize) {}
+    // panning reached `edge` of the bounds. called once, again only after the view left it.
+    fn edge_reached(&mut self, ctx: &mut Context, edge: Edge) {}
+    // `ctx.selection_range()` changed. resolve it to characters and call `ctx.set_text_selection`.
+    fn selection_changed(&mut self, ctx: &mut Context) {}
+    // gamepad input the viewer does not use itself. the D-pad and shoulder buttons change pages,
+    // the left stick pans and the right stick zooms.
+    #[cfg(all(unix, feature="gamepad"))]
+    fn gamepad_input(&mut self, ctx: &mut Context, event: gilrs::EventType) {}
     fn title(&self) -> String { "A fantastic window!".into() }
     fn event(&mut self, ctx: &mut Context, event: Self::Event) {}
+    // an event sent as JSON from JavaScript. wrap the item in `Typed` to decode it into `Event`.
+    fn event_json(&mut self, ctx: &mut Context, json: &str) {
+        warn!("JSON events are not supported by this item");
+    }
+    // an event from `Context::queue_event` that is not an `Event`.
+    // on the web, `Typed` takes the events of the item it wraps from here.
+    fn queued_event(&mut self, ctx: &mut Context, event: Box<dyn Any>) {
+        warn!("dropping queued event of the wrong type");
+    }
     fn init(&mut self, ctx: &mut Context, sender: Emitter<Self::Event>) {}
+    // called instead of `init` when the viewer could not start
+    fn on_init_error(&mut self, err: &InitError) {
+        error!("failed to start the viewer: {}", err);
+    }
     fn idle(&mut self, ctx: &mut Context) {}
+    // called every `Context::update_interval` seconds, i.e. to update a chart
+    fn tick(&mut self, ctx: &mut Context, now: Timestamp) {}
     fn window_size_hint(&self) -> Option<Vector2F> { None }
+    fn min_window_size_hint(&self) -> Option<Vector2F> { None }
+
+    // clear color for the next frame. `None` uses `Config::background`.
+    fn background(&self, ctx: &Context) -> Option<ColorF> { None }
+}
+
+// a key by its physical position
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RawKey {
+    pub physical_key: KeyCode,
+    pub scancode: Option<u32>, // platform specific, native only
+    pub state: ElementState,
+    pub modifiers: ModifiersState,
 }
 
 impl Interactive for Scene {
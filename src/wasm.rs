@@ -1,5 +1,5 @@
 use web_sys::{
-    Window, MouseEvent, WheelEvent, KeyboardEvent, UiEvent,
+    Window, MouseEvent, PointerEvent, WheelEvent, KeyboardEvent, UiEvent,
     HtmlCanvasElement, WebGl2RenderingContext, Event,
     InputEvent,
 };
@@ -16,16 +16,21 @@ use pathfinder_renderer::{
         options::{DestFramebuffer, RendererOptions, RendererMode},
     },
     concurrent::executor::SequentialExecutor,
-    options::{BuildOptions, RenderTransform, RenderCommandListener},
+    options::{RenderTransform, RenderCommandListener},
 };
 use pathfinder_webgl::WebGlDevice;
 use std::marker::PhantomData;
-use crate::util::round_v_to_16;
+use crate::util::{round_v_to_tile, tile_size, render_view_box, check_resources};
+use crate::view::{ModifiersState, ElementState, KeyEvent, Theme, RawKey, CursorGrabMode};
+use serde::de::DeserializeOwned;
 
 pub struct Emitter<T>(PhantomData<T>);
 
 pub struct Backend {}
 impl Backend {
+    pub fn time(&self) -> Duration {
+        Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+    }
     pub fn resize(&mut self, size: Vector2F) {}
     pub fn get_scroll_factors(&self) -> (Vector2F, Vector2F) {
         (
@@ -34,6 +39,25 @@ impl Backend {
         )
     }
     pub fn set_icon(&mut self, icon: Icon) {}
+    pub fn open_url(&self, url: &str) {
+        if let Err(e) = web_sys::window().unwrap().open_with_url(url) {
+            warn!("failed to open {}: {:?}", url, e);
+        }
+    }
+    pub fn set_post_process(&mut self, effect: Option<PostProcess>) {}
+    pub fn set_debug_ui(&mut self, show: bool) {}
+    // the renderer belongs to `WasmView`, shaders are compiled with the first frame
+    pub fn prewarm(&mut self) {}
+    pub fn invalidate_render_cache(&mut self) {}
+    pub fn render_cache_stats(&self) -> RenderCacheStats { RenderCacheStats::default() }
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {}
+    pub fn set_cursor_visible(&mut self, visible: bool) {}
+    pub fn set_cursor_grab(&mut self, mode: CursorGrabMode) {}
+    pub fn set_maximized(&mut self, maximized: bool) {}
+    pub fn set_minimized(&mut self, minimized: bool) {}
+    pub fn is_maximized(&self) -> bool { false }
+    pub fn set_min_window_size(&mut self, size: Option<Vector2F>) {}
+    pub fn set_max_window_size(&mut self, size: Option<Vector2F>) {}
 }
 
 #[wasm_bindgen]
@@ -44,9 +68,12 @@ pub struct WasmView {
     renderer: Renderer<WebGlDevice>,
     framebuffer_size: Vector2F,
     canvas: HtmlCanvasElement,
+    context_lost: bool,
 }
 
 impl WasmView {
+    // like all other input, the `webglcontextlost` and `webglcontextrestored` events of the canvas
+    // have to be forwarded by the page, to `context_lost` and `context_restored`.
     pub fn new(canvas: HtmlCanvasElement, context: WebGl2RenderingContext, config: Config, mut item: Box<dyn Interactive<Event=Vec<u8>>>) -> Self {
         canvas.set_attribute("tabindex", "0").unwrap();
         canvas.set_attribute("contenteditable", "true").unwrap();
@@ -65,18 +92,11 @@ impl WasmView {
 
         set_canvas_size(&canvas, ctx.window_size, framebuffer_size.to_i32());
 
-        let render_mode = RendererMode { level: ctx.config.render_level };
-        let render_options = RendererOptions {
-            dest:  DestFramebuffer::full_window(framebuffer_size.to_i32()),
-            background_color: Some(ctx.config.background),
-            show_debug_ui: false,
-        };
-
-        let renderer = Renderer::new(WebGlDevice::new(context),
-            &*ctx.config.resource_loader,
-            render_mode,
-            render_options,
-        );
+        if let Err(e) = check_resources(&*ctx.config.resource_loader, ctx.config.render_level) {
+            item.on_init_error(&e);
+            wasm_bindgen::throw_str(&e.to_string());
+        }
+        let renderer = create_renderer(context, &ctx.config, framebuffer_size);
 
         item.init(&mut ctx, Emitter(PhantomData));
 
@@ -87,8 +107,85 @@ impl WasmView {
             renderer,
             canvas,
             framebuffer_size,
+            context_lost: false,
+        }
+    }
+}
+
+// wraps an item with a typed `Event` for use in `WasmView`.
+// bytes from `WasmView::data` are decoded with bincode, strings from `WasmView::event_json` as JSON.
+pub struct Typed<T>(pub T);
+
+impl<T: Interactive> Interactive for Typed<T> where T::Event: DeserializeOwned {
+    type Event = Vec<u8>;
+
+    fn event(&mut self, ctx: &mut Context, data: Vec<u8>) {
+        match bincode::deserialize(&data) {
+            Ok(event) => self.0.event(ctx, event),
+            Err(e) => warn!("failed to decode event: {}", e),
         }
     }
+    fn event_json(&mut self, ctx: &mut Context, json: &str) {
+        match serde_json::from_str(json) {
+            Ok(event) => self.0.event(ctx, event),
+            Err(e) => warn!("failed to decode event: {}", e),
+        }
+    }
+    fn queued_event(&mut self, ctx: &mut Context, event: Box<dyn std::any::Any>) {
+        match event.downcast::<T::Event>() {
+            Ok(event) => self.0.event(ctx, *event),
+            Err(event) => self.0.queued_event(ctx, event),
+        }
+    }
+    fn init(&mut self, ctx: &mut Context, _sender: Emitter<Vec<u8>>) {
+        self.0.init(ctx, Emitter(PhantomData));
+    }
+    fn on_init_error(&mut self, err: &InitError) { self.0.on_init_error(err) }
+
+    fn scene(&mut self, ctx: &mut Context) -> Scene { self.0.scene(ctx) }
+    fn scene_for_page(&mut self, ctx: &mut Context, page: usize) -> Scene { self.0.scene_for_page(ctx, page) }
+    fn transform_scene(&mut self, ctx: &mut Context, scene: Scene) -> Scene { self.0.transform_scene(ctx, scene) }
+    fn char_input(&mut self, ctx: &mut Context, input: char) { self.0.char_input(ctx, input) }
+    fn text_input(&mut self, ctx: &mut Context, input: String) { self.0.text_input(ctx, input) }
+    fn keyboard_input(&mut self, ctx: &mut Context, modifiers: ModifiersState, event: KeyEvent) { self.0.keyboard_input(ctx, modifiers, event) }
+    fn raw_key_input(&mut self, ctx: &mut Context, key: RawKey) { self.0.raw_key_input(ctx, key) }
+    fn image_pasted(&mut self, ctx: &mut Context, image: RawImage) { self.0.image_pasted(ctx, image) }
+    fn mouse_input(&mut self, ctx: &mut Context, page: usize, pos: Vector2F, state: ElementState, modifiers: ModifiersState) {
+        self.0.mouse_input(ctx, page, pos, state, modifiers)
+    }
+    fn cursor_moved(&mut self, ctx: &mut Context, pos: Vector2F) { self.0.cursor_moved(ctx, pos) }
+    fn raw_mouse_motion(&mut self, ctx: &mut Context, delta: Vector2F) { self.0.raw_mouse_motion(ctx, delta) }
+    fn pointer_input(&mut self, ctx: &mut Context, page: usize, pos: Vector2F, pressure: f32, state: ElementState) {
+        self.0.pointer_input(ctx, page, pos, pressure, state)
+    }
+    fn exit(&mut self, ctx: &mut Context) { self.0.exit(ctx) }
+    fn release_gpu_resources(&mut self, ctx: &mut Context) { self.0.release_gpu_resources(ctx) }
+    fn should_close(&mut self, ctx: &mut Context) -> bool { self.0.should_close(ctx) }
+    fn theme_changed(&mut self, ctx: &mut Context, theme: Theme) { self.0.theme_changed(ctx, theme) }
+    fn page_changed(&mut self, ctx: &mut Context, old: usize, new: usize) { self.0.page_changed(ctx, old, new) }
+    fn edge_reached(&mut self, ctx: &mut Context, edge: Edge) { self.0.edge_reached(ctx, edge) }
+    fn selection_changed(&mut self, ctx: &mut Context) { self.0.selection_changed(ctx) }
+    fn title(&self) -> String { self.0.title() }
+    fn idle(&mut self, ctx: &mut Context) { self.0.idle(ctx) }
+    fn tick(&mut self, ctx: &mut Context, now: Timestamp) { self.0.tick(ctx, now) }
+    fn window_size_hint(&self) -> Option<Vector2F> { self.0.window_size_hint() }
+    fn min_window_size_hint(&self) -> Option<Vector2F> { self.0.min_window_size_hint() }
+    fn background(&self, ctx: &Context) -> Option<ColorF> { self.0.background(ctx) }
+}
+
+fn create_renderer(context: WebGl2RenderingContext, config: &Config, framebuffer_size: Vector2F) -> Renderer<WebGlDevice> {
+    let render_mode = RendererMode { level: config.render_level };
+    let render_options = RendererOptions {
+        dest:  DestFramebuffer::full_window(framebuffer_size.to_i32()),
+        background_color: if config.clear { Some(config.background) } else { None },
+        show_debug_ui: config.debug_ui,
+    };
+
+    Renderer::new(WebGlDevice::new(context),
+        &*config.resource_loader,
+        render_mode,
+        render_options,
+    )
 }
 
 fn v_ceil(v: Vector2F) -> Vector2F {
@@ -98,7 +195,20 @@ fn v_ceil(v: Vector2F) -> Vector2F {
 #[wasm_bindgen]
 impl WasmView {
     pub fn render(&mut self) {
-        let mut scene = self.item.scene(&mut self.ctx);
+        if self.context_lost {
+            return;
+        }
+        let opacity = self.ctx.window_opacity().to_string();
+        let _ = self.canvas.style().set_property("opacity", &opacity);
+        let background = self.item.background(&self.ctx).unwrap_or(self.ctx.config.background);
+        if self.ctx.config.clear {
+            self.renderer.options_mut().background_color = Some(background);
+        }
+        self.renderer.options_mut().show_debug_ui = self.ctx.config.debug_ui;
+
+        let page = self.ctx.page_nr;
+        let scene = self.ctx.page_or_item_scene(&mut *self.item, page);
+        let mut scene = self.item.transform_scene(&mut self.ctx, scene);
         let scene_view_box = view_box(&scene);
 
         // figure out the framebuffer, as that can only be integer values
@@ -114,29 +224,49 @@ impl WasmView {
         }
 
         // temp fix
-        scene.set_view_box(RectF::new(Vector2F::default(), round_v_to_16(framebuffer_size.to_i32()).to_f32()));
+        let padded_size = round_v_to_tile(framebuffer_size.to_i32(), tile_size(self.ctx.config.render_level));
+        scene.set_view_box(render_view_box(framebuffer_size.to_i32(), padded_size, self.ctx.config.exact_size));
         
         let tr = if self.ctx.config.pan {
-            Transform2F::from_translation(self.ctx.window_size * 0.5) *
+            Transform2F::from_translation(self.ctx.viewport().center()) *
             Transform2F::from_translation(-self.ctx.view_center)
         } else {
             Transform2F::from_translation(-scene_view_box.origin())
         };
-        let options = BuildOptions {
-            transform: RenderTransform::Transform2D(tr),
-            dilation: Vector2F::default(),
-            subpixel_aa_enabled: false
-        };
+        let area = tr.inverse() * RectF::new(Vector2F::default(), framebuffer_size);
+        self.ctx.decorate_scene(&mut scene, area, background, tr.inverse());
+        let options = self.ctx.config.text_aa.build_options(RenderTransform::Transform2D(tr));
 
         scene.build_and_render(&mut self.renderer, options, SequentialExecutor);
         self.ctx.redraw_requested = false;
+        self.ctx.frame_rendered = true;
     }
     pub fn animation_frame(&mut self, timestamp: f64) {
         self.render();
     }
 
     pub fn mouse_move(&mut self, event: &MouseEvent) -> bool {
-        false
+        // keeps coming with pointer lock, while the position stays
+        let delta = Vector2F::new(event.movement_x() as f32, event.movement_y() as f32);
+        self.item.raw_mouse_motion(&mut self.ctx, delta);
+        let pos = Vector2F::new(event.offset_x() as f32, event.offset_y() as f32);
+        self.ctx.cursor_pos = Some(pos);
+        if let Some(idx) = self.ctx.viewport_at(pos) {
+            self.ctx.activate_viewport(idx);
+        }
+        if self.ctx.selecting {
+            let scene_pos = self.scene_pos(event);
+            self.ctx.update_selection(scene_pos);
+            self.ctx.dispatch_notifications(&mut *self.item);
+        }
+        // `buttons` is a bit mask, 1 for the left and 2 for the right button
+        let primary = if self.ctx.config.swap_mouse_buttons { 2 } else { 1 };
+        if event.buttons() & primary != 0 {
+            let scene_pos = self.scene_pos(event);
+            let page = self.ctx.page_nr;
+            self.item.pointer_input(&mut self.ctx, page, scene_pos, 1.0, ElementState::Pressed);
+        }
+        self.ctx.redraw_requested
     }
 
     pub fn mouse_down(&mut self, event: &MouseEvent) -> bool {
@@ -148,21 +278,60 @@ impl WasmView {
         self.ctx.redraw_requested
     }
 
-    fn mouse_input(&mut self, event: &MouseEvent, state: ElementState) {
+    fn scene_pos(&self, event: &MouseEvent) -> Vector2F {
         let css_pos = Vector2F::new(event.offset_x() as f32, event.offset_y() as f32);
 
         let scale = 1.0 / self.ctx.scale;
         let tr = if self.ctx.config.pan {
             Transform2F::from_translation(self.ctx.view_center) *
             Transform2F::from_scale(Vector2F::splat(scale)) *
-            Transform2F::from_translation(self.ctx.window_size * (-0.5 * self.ctx.scale_factor))
+            Transform2F::from_translation(self.ctx.viewport().center() * -self.ctx.scale_factor)
         } else {
             Transform2F::from_scale(Vector2F::splat(scale))
         };
 
-        let scene_pos = tr * css_pos;
+        tr * css_pos
+    }
+
+    fn mouse_input(&mut self, event: &MouseEvent, state: ElementState) {
+        let scene_pos = self.scene_pos(event);
+        // `button()` is 0 for the left and 2 for the right button
+        let primary = if self.ctx.config.swap_mouse_buttons { 2 } else { 0 };
+        if self.ctx.config.text_selection && event.button() == primary && !event.shift_key() {
+            match state {
+                ElementState::Pressed => self.ctx.begin_selection(scene_pos),
+                ElementState::Released => self.ctx.end_selection(),
+            }
+            self.ctx.dispatch_notifications(&mut *self.item);
+        }
+        let page = self.ctx.page_nr;
+        self.item.mouse_input(&mut self.ctx, page, scene_pos, state, mouse_modifiers(event));
+        self.item.pointer_input(&mut self.ctx, page, scene_pos, 1.0, state);
+    }
+
+    // pen and touch input. mice already go through `mouse_down` and friends and are ignored here.
+    pub fn pointer_down(&mut self, event: &PointerEvent) -> bool {
+        self.pointer_input(event, ElementState::Pressed);
+        self.ctx.redraw_requested
+    }
+    pub fn pointer_move(&mut self, event: &PointerEvent) -> bool {
+        // only while pressed, `buttons` is 0 for a hovering pen
+        if event.buttons() != 0 {
+            self.pointer_input(event, ElementState::Pressed);
+        }
+        self.ctx.redraw_requested
+    }
+    pub fn pointer_up(&mut self, event: &PointerEvent) -> bool {
+        self.pointer_input(event, ElementState::Released);
+        self.ctx.redraw_requested
+    }
+    fn pointer_input(&mut self, event: &PointerEvent, state: ElementState) {
+        if event.pointer_type() == "mouse" {
+            return;
+        }
+        let scene_pos = self.scene_pos(event);
         let page = self.ctx.page_nr;
-        self.item.mouse_input(&mut self.ctx, page, scene_pos, state);
+        self.item.pointer_input(&mut self.ctx, page, scene_pos, event.pressure(), state);
     }
 
     pub fn wheel(&mut self, event: &WheelEvent) -> bool {
@@ -184,6 +353,26 @@ impl WasmView {
             Some(keycode) => keycode,
             None => return,
         };
+        if state == ElementState::Pressed && event.ctrl_key() && event.shift_key()
+            && self.ctx.config.copy_image_key == Some(keycode) {
+            cancel(&event);
+            self.copy_image();
+            return;
+        }
+        if state == ElementState::Pressed && self.ctx.config.debug_ui_key == Some(keycode) {
+            cancel(&event);
+            self.ctx.toggle_debug_ui();
+            return;
+        }
+        // `code` names the physical key, `key` the character
+        let raw_key = RawKey {
+            physical_key: keycode,
+            scancode: None,
+            state,
+            modifiers: keyboard_modifiers_state(&event),
+        };
+        self.item.raw_key_input(&mut self.ctx, raw_key);
+
         let mut key_event = KeyEvent {
             cancelled: false,
             modifiers: keyboard_modifiers(&event),
@@ -198,24 +387,137 @@ impl WasmView {
         }
     }
 
+    // copy the canvas to the clipboard as PNG, using the async clipboard API
+    fn copy_image(&mut self) {
+        // the drawing buffer is only valid right after rendering
+        self.render();
+        let on_blob = Closure::once(|blob: JsValue| {
+            if let Err(e) = write_clipboard_image(blob) {
+                warn!("failed to copy the view to the clipboard: {:?}", e);
+            }
+        });
+        if let Err(e) = self.canvas.to_blob(on_blob.as_ref().unchecked_ref()) {
+            warn!("failed to capture the canvas: {:?}", e);
+        }
+        on_blob.forget();
+    }
+
+    // to be called from a `webglcontextlost` listener. it prevents the default,
+    // without that the browser never restores the context. rendering stops until `context_restored`.
+    pub fn context_lost(&mut self, event: &Event) -> bool {
+        cancel(event);
+        self.context_lost = true;
+        false
+    }
+    // to be called from a `webglcontextrestored` listener.
+    // the old renderer refers to GPU objects that are gone, so build a new one.
+    pub fn context_restored(&mut self, event: &Event) -> bool {
+        let context = self.canvas.get_context("webgl2").unwrap().unwrap()
+            .dyn_into::<WebGl2RenderingContext>().unwrap();
+        self.renderer = create_renderer(context, &self.ctx.config, self.framebuffer_size);
+        self.context_lost = false;
+        self.ctx.request_redraw();
+        self.ctx.redraw_requested
+    }
+
     pub fn resize(&mut self, event: &UiEvent) -> bool {
         self.ctx.set_scale_factor(scale_factor(&self.window));
         self.ctx.request_redraw();
         self.ctx.redraw_requested
     }
 
+    // a JSON encoded event, see `Typed`
+    pub fn event_json(&mut self, json: String) -> bool {
+        self.item.event_json(&mut self.ctx, &json);
+        self.ctx.redraw_requested
+    }
+
     pub fn data(&mut self, data: &Uint8Array) -> bool {
         self.item.event(&mut self.ctx, data.to_vec());
         self.ctx.redraw_requested
     }
     pub fn idle(&mut self) -> bool {
-        self.item.idle(&mut self.ctx);
+        self.ctx.dispatch_queued_events(&mut *self.item);
+        if self.ctx.take_idle() {
+            self.item.idle(&mut self.ctx);
+        }
+        self.ctx.dispatch_notifications(&mut *self.item);
+        self.ctx.update_track();
+        self.ctx.redraw_requested
+    }
+    // call every `update_interval` seconds (see `update_interval()`) with `performance.now()`
+    pub fn tick(&mut self, now: f64) -> bool {
+        self.item.tick(&mut self.ctx, now);
+        self.ctx.redraw_requested
+    }
+    pub fn update_interval(&self) -> Option<f32> {
+        self.ctx.update_interval
+    }
+    // seconds until a redraw asked for by `Context::request_redraw_after`.
+    // set a timer for it and call `redraw_timer` when it fires.
+    pub fn redraw_delay(&mut self) -> Option<f32> {
+        self.ctx.check_redraw_timer().map(|dt| dt.as_secs_f32())
+    }
+    pub fn redraw_timer(&mut self) -> bool {
+        self.ctx.check_redraw_timer();
         self.ctx.redraw_requested
     }
     pub fn input(&mut self, text: String) -> bool {
         self.item.text_input(&mut self.ctx, text);
         self.ctx.redraw_requested
     }
+
+    // RGBA pixels of a pasted image. the clipboard API hands out encoded images asynchronously,
+    // so the page decodes them: take the "image/png" type of a ClipboardItem (or a File of the paste event),
+    // draw it with createImageBitmap onto a canvas and pass getImageData().data here.
+    pub fn image_pasted(&mut self, rgba: &Uint8Array, width: u32, height: u32) -> bool {
+        let format = PixelFormat::Rgba8;
+        let data = rgba.to_vec();
+        let stride = width * format.bytes_per_pixel();
+        if data.len() < (stride * height) as usize {
+            warn!("pasted image is too small for {}x{}", width, height);
+            return false;
+        }
+        let image = RawImage { data, width, height, stride, format };
+        self.item.image_pasted(&mut self.ctx, image);
+        self.ctx.redraw_requested
+    }
+
+    // call from a `beforeunload` listener. if the item does not want to close,
+    // the browser asks the user whether to leave the page.
+    pub fn before_unload(&mut self, event: &Event) {
+        if !self.item.should_close(&mut self.ctx) {
+            cancel(event);
+            // older browsers only look at returnValue
+            let _ = js_sys::Reflect::set(event, &JsValue::from_str("returnValue"), &JsValue::from_str(""));
+        }
+    }
+    // true once the item called `Context::close`, the page should then drop the view
+    pub fn close_requested(&self) -> bool {
+        self.ctx.close
+    }
+    // tear down the item before the view is dropped
+    pub fn exit(&mut self) {
+        self.item.exit(&mut self.ctx);
+        self.item.release_gpu_resources(&mut self.ctx);
+    }
+}
+
+// navigator.clipboard.write([new ClipboardItem({"image/png": blob})])
+fn write_clipboard_image(blob: JsValue) -> Result<(), JsValue> {
+    use js_sys::{Reflect, Object, Array};
+
+    let window = web_sys::window().unwrap();
+    let clipboard = Reflect::get(&window.navigator(), &"clipboard".into())?;
+    let clipboard_item: Function = Reflect::get(&window, &"ClipboardItem".into())?.dyn_into()?;
+
+    let data = Object::new();
+    Reflect::set(&data, &"image/png".into(), &blob)?;
+    let item = Reflect::construct(&clipboard_item, &Array::of1(&data))?;
+
+    let write: Function = Reflect::get(&clipboard, &"write".into())?.dyn_into()?;
+    write.call1(&clipboard, &Array::of1(&item))?;
+    Ok(())
 }
 
 fn cancel(event: impl AsRef<Event>) {
@@ -251,13 +553,13 @@ pub fn window_size(window: &Window) -> Vector2F {
     Vector2F::new(width as f32, height as f32)
 }
 
-pub fn mouse_modifiers(event: &MouseEvent) -> Modifiers {
-    Modifiers {
-        shift: event.shift_key(),
-        ctrl: event.ctrl_key(),
-        alt: event.alt_key(),
-        meta: event.meta_key()
-    }
+pub fn mouse_modifiers(event: &MouseEvent) -> ModifiersState {
+    let mut modifiers = ModifiersState::empty();
+    modifiers.set(ModifiersState::SHIFT, event.shift_key());
+    modifiers.set(ModifiersState::CONTROL, event.ctrl_key());
+    modifiers.set(ModifiersState::ALT, event.alt_key());
+    modifiers.set(ModifiersState::SUPER, event.meta_key());
+    modifiers
 }
 
 pub fn virtual_key_code(event: &KeyboardEvent) -> Option<KeyCode> {
@@ -423,6 +725,15 @@ pub fn virtual_key_code(event: &KeyboardEvent) -> Option<KeyCode> {
     })
 }
 
+pub fn keyboard_modifiers_state(event: &KeyboardEvent) -> ModifiersState {
+    let mut modifiers = ModifiersState::empty();
+    modifiers.set(ModifiersState::SHIFT, event.shift_key());
+    modifiers.set(ModifiersState::CONTROL, event.ctrl_key());
+    modifiers.set(ModifiersState::ALT, event.alt_key());
+    modifiers.set(ModifiersState::SUPER, event.meta_key());
+    modifiers
+}
+
 pub fn keyboard_modifiers(event: &KeyboardEvent) -> Modifiers {
     Modifiers {
         shift: event.shift_key(),